@@ -0,0 +1,3 @@
+alter table users
+    add column role text NOT NULL DEFAULT 'user'
+    CHECK (role IN ('user', 'moderator', 'admin'));
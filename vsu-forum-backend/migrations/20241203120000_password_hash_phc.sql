@@ -0,0 +1,5 @@
+-- password hashes are stored as PHC strings now; old unsalted SHA3 hashes are
+-- kept with the `$sha3$` prefix and replaced with Argon2id ones on next login
+alter table users
+    alter column password_hash type text
+    using '$sha3$' || encode(password_hash, 'hex');
@@ -0,0 +1,10 @@
+alter table reports
+    add column created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+alter table bookmarks
+    add column created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+create index topics_created_at_id_idx on topics(created_at, id);
+create index posts_topic_id_created_at_id_idx on posts(topic_id, created_at, id);
+create index reports_created_at_id_idx on reports(created_at, id);
+create index bookmarks_user_id_created_at_topic_id_idx on bookmarks(user_id, created_at, topic_id);
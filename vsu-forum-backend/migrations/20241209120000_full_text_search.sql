@@ -0,0 +1,17 @@
+-- cyrillic words go through the russian stemmer and latin ones through the
+-- english stemmer, so both "лекции" and "lectures" are found by their stems
+create text search configuration forum (copy = russian);
+
+alter text search configuration forum
+    alter mapping for asciiword, asciihword, hword_asciipart with english_stem;
+
+alter table topics
+    add column search_vector tsvector NOT NULL
+    GENERATED ALWAYS AS (to_tsvector('forum', name)) STORED;
+
+alter table posts
+    add column search_vector tsvector NOT NULL
+    GENERATED ALWAYS AS (to_tsvector('forum', text)) STORED;
+
+create index topics_search_vector_idx on topics using gin(search_vector);
+create index posts_search_vector_idx on posts using gin(search_vector);
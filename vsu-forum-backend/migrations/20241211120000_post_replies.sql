@@ -0,0 +1,4 @@
+alter table posts
+    add column reply_to_post_id BIGINT references posts(id) ON DELETE SET NULL;
+
+create index posts_reply_to_post_id_idx on posts(reply_to_post_id);
@@ -0,0 +1,14 @@
+alter table posts
+    add column edited_at TIMESTAMP WITH TIME ZONE;
+
+-- every version of an edited post including the original one; posts that
+-- were never edited have no revisions
+create table post_revisions (
+    id BIGINT NOT NULL PRIMARY KEY GENERATED ALWAYS AS IDENTITY,
+    post_id BIGINT NOT NULL references posts(id) ON DELETE CASCADE,
+    editor_id BIGINT references users(id) ON DELETE SET NULL,
+    text text NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+create index post_revisions_post_id_idx on post_revisions(post_id, created_at);
@@ -0,0 +1,10 @@
+alter table topics
+    add column deleted_at TIMESTAMP WITH TIME ZONE,
+    add column deleted_by BIGINT references users(id) ON DELETE SET NULL;
+
+alter table posts
+    add column deleted_at TIMESTAMP WITH TIME ZONE,
+    add column deleted_by BIGINT references users(id) ON DELETE SET NULL;
+
+create index topics_deleted_at_idx on topics(deleted_at) where deleted_at is not null;
+create index posts_deleted_at_idx on posts(deleted_at) where deleted_at is not null;
@@ -0,0 +1,9 @@
+alter table reports
+    add column status text NOT NULL DEFAULT 'open'
+        CHECK (status IN ('open', 'in_review', 'resolved', 'rejected')),
+    add column assignee_id BIGINT references users(id) ON DELETE SET NULL,
+    add column resolution_note text,
+    add column updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    add column resolved_at TIMESTAMP WITH TIME ZONE;
+
+create index reports_status_created_at_id_idx on reports(status, created_at, id);
@@ -0,0 +1,6 @@
+alter table reports
+    add column target_type text NOT NULL DEFAULT 'user'
+        CHECK (target_type IN ('user', 'post', 'topic')),
+    add column post_id BIGINT references posts(id) ON DELETE SET NULL,
+    add column topic_id BIGINT references topics(id) ON DELETE SET NULL,
+    add column content_snapshot text;
@@ -0,0 +1,13 @@
+create table user_restrictions (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL references users(id) ON DELETE CASCADE,
+    kind text NOT NULL CHECK (kind IN ('ban', 'mute')),
+    reason text NOT NULL,
+    issued_by BIGINT references users(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    -- null means the restriction is permanent
+    expires_at TIMESTAMP WITH TIME ZONE,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+create index user_restrictions_active_idx on user_restrictions(user_id, kind) where revoked_at is null;
@@ -0,0 +1,26 @@
+-- actor_id intentionally has no foreign key: entries must outlive the users
+-- they mention, and the table must never be updated
+create table audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    actor_id BIGINT NOT NULL,
+    action text NOT NULL,
+    target_type text NOT NULL,
+    target_id BIGINT NOT NULL,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+create index audit_log_created_at_id_idx on audit_log(created_at, id);
+create index audit_log_actor_id_idx on audit_log(actor_id);
+create index audit_log_target_idx on audit_log(target_type, target_id);
+
+create function audit_log_append_only() returns trigger as $$
+begin
+    raise exception 'audit_log is append-only';
+end;
+$$ language plpgsql;
+
+create trigger audit_log_append_only
+    before update or delete or truncate on audit_log
+    for each statement execute function audit_log_append_only();
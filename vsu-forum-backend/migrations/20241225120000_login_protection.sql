@@ -0,0 +1,12 @@
+create table login_failures (
+    scope text NOT NULL CHECK (scope IN ('login', 'ip')),
+    key text NOT NULL,
+    failures INT NOT NULL,
+    last_failed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    locked_until TIMESTAMP WITH TIME ZONE,
+    PRIMARY KEY (scope, key)
+);
+
+alter table users
+    add column last_login_at TIMESTAMP WITH TIME ZONE,
+    add column last_login_ip text;
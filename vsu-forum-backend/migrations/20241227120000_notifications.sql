@@ -0,0 +1,14 @@
+create table notifications (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL references users(id) ON DELETE CASCADE,
+    kind text NOT NULL CHECK (kind IN ('bookmarked_topic_post', 'reply', 'mention', 'reaction')),
+    actor_id BIGINT references users(id) ON DELETE SET NULL,
+    topic_id BIGINT NOT NULL references topics(id) ON DELETE CASCADE,
+    post_id BIGINT NOT NULL references posts(id) ON DELETE CASCADE,
+    reaction_id BIGINT references available_reactions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    read_at TIMESTAMP WITH TIME ZONE
+);
+
+create index notifications_user_id_created_at_id_idx on notifications(user_id, created_at, id);
+create index notifications_unread_idx on notifications(user_id) where read_at is null;
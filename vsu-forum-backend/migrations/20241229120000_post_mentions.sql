@@ -0,0 +1,7 @@
+create table post_mentions (
+    post_id BIGINT NOT NULL references posts(id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL references users(id) ON DELETE CASCADE,
+    PRIMARY KEY (post_id, user_id)
+);
+
+create index post_mentions_user_id_idx on post_mentions(user_id);
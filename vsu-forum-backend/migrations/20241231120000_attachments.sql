@@ -0,0 +1,16 @@
+create table attachments (
+    id BIGSERIAL PRIMARY KEY,
+    post_id BIGINT NOT NULL references posts(id) ON DELETE CASCADE,
+    uploader_id BIGINT NOT NULL references users(id) ON DELETE CASCADE,
+    file_name text NOT NULL CHECK (length(file_name) BETWEEN 1 AND 255),
+    content_type text NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes > 0),
+    -- dimensions and thumbnail are set for images only
+    width INT,
+    height INT,
+    storage_key text NOT NULL UNIQUE,
+    thumbnail_key text UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+);
+
+create index attachments_post_id_idx on attachments(post_id);
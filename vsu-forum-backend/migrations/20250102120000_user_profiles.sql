@@ -0,0 +1,20 @@
+alter table users
+    add column display_name text CHECK (length(display_name) BETWEEN 1 AND 64),
+    add column bio text CHECK (length(bio) <= 2000),
+    -- avatars are stored resized to a PNG thumbnail only
+    add column avatar_key text UNIQUE,
+    add column created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    add column last_seen_at TIMESTAMP WITH TIME ZONE;
+
+-- registration time was not recorded before, the earliest known activity of
+-- the user is the closest guess
+update users u set
+    created_at = coalesce(
+        least(
+            (select min(created_at) from sessions where user_id = u.id),
+            (select min(created_at) from topics where author_id = u.id),
+            (select min(created_at) from posts where author_id = u.id)
+        ),
+        u.created_at
+    ),
+    last_seen_at = last_login_at;
@@ -0,0 +1,13 @@
+create table password_reset_tokens (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL references users(id) ON DELETE CASCADE,
+    token_hash text NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+create index password_reset_tokens_user_id_idx on password_reset_tokens(user_id);
+
+-- deleted accounts are kept anonymised, so their topics and posts stay
+alter table users add column deleted_at TIMESTAMP WITH TIME ZONE;
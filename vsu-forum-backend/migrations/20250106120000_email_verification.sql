@@ -0,0 +1,19 @@
+alter table users add column email text;
+alter table users add column email_verified_at TIMESTAMP WITH TIME ZONE;
+
+-- an address may wait for verification in several accounts, but belongs to
+-- the one that verifies it first
+create unique index users_verified_email_idx on users(lower(email))
+    where email_verified_at is not null;
+
+create table email_verification_tokens (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL references users(id) ON DELETE CASCADE,
+    email text NOT NULL,
+    token_hash text NOT NULL UNIQUE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+create index email_verification_tokens_user_id_idx on email_verification_tokens(user_id);
@@ -0,0 +1,2 @@
+-- expired failures are deleted regularly
+create index login_failures_last_failed_at_idx on login_failures(last_failed_at);
@@ -0,0 +1,13 @@
+-- toggling a reaction must not notify the author again
+delete from notifications n
+using notifications earlier
+where n.kind = 'reaction' and earlier.kind = 'reaction'
+and n.user_id = earlier.user_id
+and n.post_id = earlier.post_id
+and n.actor_id = earlier.actor_id
+and n.reaction_id = earlier.reaction_id
+and n.id > earlier.id;
+
+create unique index notifications_reaction_unique_idx
+on notifications(user_id, post_id, actor_id, reaction_id)
+where kind = 'reaction';
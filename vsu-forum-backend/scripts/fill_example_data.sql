@@ -6,8 +6,11 @@ BEGIN
 END;
 $$ language 'plpgsql' STRICT;
 
+-- every user has the password "password"
 INSERT INTO users (login, password_hash)
-SELECT FORMAT('user%s', t.i), 'password'::bytea
+SELECT
+    FORMAT('user%s', t.i),
+    '$argon2id$v=19$m=19456,t=2,p=1$5IFjq8WJnYcEgSKWLmdErw$LzEXxS62Odey1OPN0z+D3Q1U/kf+ZL33fJWYYLrjJ5I'
 FROM generate_series(1, 10000) as t(i);
 
 INSERT INTO topics_categories (name) VALUES
@@ -24,14 +27,19 @@ INSERT INTO posts (topic_id, author_id, text)
 SELECT t.i, random_between(1, 1000), 'text'
 FROM generate_series(1, 1000) as t(i);
 
-INSERT INTO reports (author_id, reported_user_id)
-SELECT t.i, random_between(1, 1000)
+INSERT INTO reports (author_id, reported_user_id, reason)
+SELECT t.i, random_between(1, 1000), 'spam'
 FROM generate_series(1, 1000) as t(i);
 
 INSERT INTO bookmarks (user_id, topic_id)
 SELECT t.i, random_between(1, 1000)
 FROM generate_series(1, 1000) as t(i);
 
-INSERT INTO reactions (message_id, author_id, reaction)
-SELECT t.i, t.i, FORMAT('emoji%s', t.i)
+INSERT INTO available_reactions (reaction) VALUES
+    ('👍'),
+    ('👎'),
+    ('😂');
+
+INSERT INTO reactions (post_id, author_id, reaction_id)
+SELECT t.i, t.i, random_between(1, 3)
 FROM generate_series(1, 1000) as t(i);
@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+
+use crate::dto::{attachments::AttachmentDTO, posts::PostDTO};
+
+/// Fills `attachments` of the posts. Attachments of removed posts are hidden
+/// together with their text.
+pub async fn attach_post_attachments<'a>(
+    db_pool: &PgPool,
+    posts: impl IntoIterator<Item = &'a mut PostDTO>,
+) -> Result<(), sqlx::Error> {
+    let posts: Vec<&mut PostDTO> = posts
+        .into_iter()
+        .filter(|post| post.deleted_at.is_none())
+        .collect();
+    if posts.is_empty() {
+        return Ok(());
+    }
+
+    let post_ids: Vec<i64> = posts.iter().map(|post| post.id).collect();
+
+    let mut attachments: HashMap<i64, Vec<AttachmentDTO>> = HashMap::new();
+    sqlx::query!(
+        r#"
+        SELECT
+            id, post_id, file_name, content_type, size_bytes, width, height, created_at,
+            thumbnail_key IS NOT NULL AS "has_thumbnail!"
+        FROM attachments
+        WHERE post_id = any($1)
+        ORDER BY id
+        "#,
+        &post_ids
+    )
+    .fetch_all(db_pool)
+    .await?
+    .into_iter()
+    .for_each(|record| {
+        attachments
+            .entry(record.post_id)
+            .or_default()
+            .push(AttachmentDTO {
+                id: record.id,
+                post_id: record.post_id,
+                file_name: record.file_name,
+                content_type: record.content_type,
+                size_bytes: record.size_bytes,
+                width: record.width,
+                height: record.height,
+                url: AttachmentDTO::url(record.id),
+                thumbnail_url: record
+                    .has_thumbnail
+                    .then(|| AttachmentDTO::thumbnail_url(record.id)),
+                created_at: record.created_at,
+            })
+    });
+
+    for post in posts {
+        if let Some(post_attachments) = attachments.remove(&post.id) {
+            post.attachments = post_attachments;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use sqlx::PgConnection;
+
+use crate::{errors::ApiError, models::AuditAction};
+
+/// Must be called in the same transaction as the action itself, so an action
+/// is never applied without its audit entry.
+pub async fn record_audit_entry<T: Serialize>(
+    conn: &mut PgConnection,
+    actor_id: i64,
+    action: AuditAction,
+    target_id: i64,
+    before: Option<&T>,
+    after: Option<&T>,
+) -> Result<(), ApiError> {
+    let before = before
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|_| ApiError::InternalServerError)?;
+    let after = after
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "
+        insert into audit_log(actor_id, action, target_type, target_id, before, after)
+        values ($1, $2, $3, $4, $5, $6)
+        ",
+        actor_id,
+        action.as_str(),
+        action.target_type(),
+        target_id,
+        before,
+        after
+    )
+    .execute(conn)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(())
+}
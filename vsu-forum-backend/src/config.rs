@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use config::Config;
 use serde::Deserialize;
 
@@ -6,6 +8,24 @@ pub struct AppConfig {
     pub db_connstring: String,
     pub logging: LoggingConfig,
     pub jwt: JwtConfig,
+    #[serde(default)]
+    pub password_hashing: PasswordHashingConfig,
+    #[serde(default)]
+    pub soft_deletion: SoftDeletionConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub login_protection: LoginProtectionConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub attachments: AttachmentsConfig,
+    #[serde(default)]
+    pub mailer: MailerConfig,
+    #[serde(default)]
+    pub password_reset: PasswordResetConfig,
+    #[serde(default)]
+    pub email_verification: EmailVerificationConfig,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -17,6 +37,233 @@ pub struct LoggingConfig {
 #[derive(Deserialize, Debug, Clone)]
 pub struct JwtConfig {
     pub secret_key: String,
+    #[serde(default = "default_access_token_ttl_minutes")]
+    pub access_token_ttl_minutes: i64,
+    #[serde(default = "default_refresh_token_ttl_days")]
+    pub refresh_token_ttl_days: i64,
+}
+
+fn default_access_token_ttl_minutes() -> i64 {
+    15
+}
+
+fn default_refresh_token_ttl_days() -> i64 {
+    31
+}
+
+/// Argon2id cost parameters; defaults are the OWASP recommended minimum.
+#[derive(Deserialize, Debug, Clone)]
+pub struct PasswordHashingConfig {
+    /// Memory size in KiB.
+    pub memory_cost: u32,
+    /// Number of iterations.
+    pub time_cost: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashingConfig {
+    fn default() -> Self {
+        Self {
+            memory_cost: 19 * 1024,
+            time_cost: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Removed topics and posts stay restorable for `retention_days`, after that
+/// they are purged for good.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SoftDeletionConfig {
+    pub retention_days: i64,
+    pub purge_interval_minutes: u64,
+}
+
+impl Default for SoftDeletionConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            purge_interval_minutes: 60,
+        }
+    }
+}
+
+/// Token buckets for write requests: `user` limits authorized users by id,
+/// `ip` limits registration and login attempts by client address.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub user: TokenBucketConfig,
+    pub ip: TokenBucketConfig,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            user: TokenBucketConfig {
+                capacity: 30,
+                refill_per_minute: 60,
+            },
+            ip: TokenBucketConfig {
+                capacity: 10,
+                refill_per_minute: 10,
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TokenBucketConfig {
+    /// Maximum burst of requests.
+    pub capacity: u32,
+    /// Tokens added back every minute.
+    pub refill_per_minute: u32,
+}
+
+/// After `max_failures_per_*` failed logins in a row the login or the client
+/// address is locked out for `base_lockout_seconds`, and every next failure
+/// doubles the lockout up to `max_lockout_seconds`. Failures older than
+/// `failure_window_minutes` are forgotten.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LoginProtectionConfig {
+    pub max_failures_per_login: i32,
+    pub max_failures_per_ip: i32,
+    pub base_lockout_seconds: i64,
+    pub max_lockout_seconds: i64,
+    pub failure_window_minutes: i32,
+}
+
+impl Default for LoginProtectionConfig {
+    fn default() -> Self {
+        Self {
+            max_failures_per_login: 5,
+            max_failures_per_ip: 20,
+            base_lockout_seconds: 30,
+            max_lockout_seconds: 3600,
+            failure_window_minutes: 60,
+        }
+    }
+}
+
+/// Where uploaded files are kept; `backend` selects the implementation.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum StorageConfig {
+    /// Files are stored in the `path` directory on the local disk.
+    Local { path: PathBuf },
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self::Local {
+            path: PathBuf::from("uploads"),
+        }
+    }
+}
+
+/// Images are checked by decoding them, other files are trusted to be of the
+/// content type sent by the client as long as it is allowed.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AttachmentsConfig {
+    pub max_file_size_bytes: usize,
+    pub max_files_per_post: i64,
+    pub allowed_content_types: Vec<String>,
+    /// Thumbnails fit into a square with this side in pixels.
+    pub thumbnail_size: u32,
+}
+
+impl Default for AttachmentsConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size_bytes: 10 * 1024 * 1024,
+            max_files_per_post: 10,
+            allowed_content_types: [
+                "image/png",
+                "image/jpeg",
+                "image/gif",
+                "image/webp",
+                "application/pdf",
+                "text/plain",
+            ]
+            .map(String::from)
+            .to_vec(),
+            thumbnail_size: 320,
+        }
+    }
+}
+
+/// How e-mails are delivered; `backend` selects the implementation.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum MailerConfig {
+    /// Mails are not sent; only their recipients and subjects are logged.
+    #[default]
+    Log,
+    /// Mails are printed to the standard output.
+    Stdout,
+    /// Mails are appended to the file at `path`.
+    File { path: PathBuf },
+    /// Mails are delivered through an SMTP relay; `from` is the sender
+    /// address.
+    Smtp(SmtpConfig),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Implicit TLS if the port is 465, STARTTLS otherwise.
+    #[serde(default = "default_smtp_tls")]
+    pub tls: bool,
+    pub from: String,
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_smtp_tls() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PasswordResetConfig {
+    pub token_ttl_minutes: i64,
+}
+
+impl Default for PasswordResetConfig {
+    fn default() -> Self {
+        Self {
+            token_ttl_minutes: 60,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct EmailVerificationConfig {
+    pub token_ttl_minutes: i64,
+    /// Users without a verified e-mail address can only read the forum.
+    pub require_verified_email: bool,
+}
+
+impl Default for EmailVerificationConfig {
+    fn default() -> Self {
+        Self {
+            token_ttl_minutes: 24 * 60,
+            require_verified_email: false,
+        }
+    }
 }
 
 pub fn load_config(path: &str) -> anyhow::Result<AppConfig> {
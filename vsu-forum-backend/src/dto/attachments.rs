@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AttachmentDTO {
+    pub id: i64,
+    pub post_id: i64,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    /// Set for images only.
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Path of the file relative to the API root.
+    pub url: String,
+    /// Path of a PNG preview relative to the API root; set for images only.
+    pub thumbnail_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AttachmentDTO {
+    pub fn url(id: i64) -> String {
+        format!("/attachments/{id}")
+    }
+
+    pub fn thumbnail_url(id: i64) -> String {
+        format!("/attachments/{id}/thumbnail")
+    }
+}
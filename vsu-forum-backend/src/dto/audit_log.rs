@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AuditLogEntryDTO {
+    pub id: i64,
+    pub actor_id: i64,
+    /// Null if the actor's account no longer exists.
+    pub actor_login: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: i64,
+    /// State of the target before and after the action; null when the target
+    /// did not exist at that moment.
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+pub struct GetAuditLogDTO {
+    pub actor_id: Option<i64>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<i64>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+}
@@ -1,6 +1,8 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct BookmarkDTO {
     pub topic_id: i64,
+    pub created_at: DateTime<Utc>,
 }
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::Role;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
     pub user_id: i64,
+    pub session_id: i64,
+    #[serde(default)]
+    pub role: Role,
 }
@@ -1,13 +1,20 @@
+pub mod attachments;
+pub mod audit_log;
 pub mod available_reactions;
 pub mod bookmarks;
 pub mod claims;
 pub mod common;
 pub mod errors;
+pub mod notifications;
+pub mod pagination;
+pub mod post_revisions;
 pub mod posts;
 pub mod reactions;
 pub mod reports;
+pub mod restrictions;
 pub mod search;
 pub mod stats;
 pub mod topic_categories;
+pub mod topic_events;
 pub mod topics;
 pub mod users;
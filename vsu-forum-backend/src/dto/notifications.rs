@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::NotificationKind;
+
+use super::users::UserDTO;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotificationDTO {
+    pub id: i64,
+    pub kind: NotificationKind,
+    /// User who wrote the post or set the reaction.
+    pub actor: Option<UserDTO>,
+    pub topic_id: i64,
+    pub topic_name: String,
+    pub post_id: i64,
+    /// Set for reaction notifications.
+    pub reaction: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+pub struct GetNotificationsDTO {
+    #[serde(default)]
+    pub unread_only: bool,
+}
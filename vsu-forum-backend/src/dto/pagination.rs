@@ -0,0 +1,199 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use validator::Validate;
+
+const DEFAULT_LIMIT: i64 = 20;
+
+/// Offset pagination is used only where there is no stable order to put a
+/// cursor on, and deep pages are not served: each of them costs a scan of all
+/// rows before it.
+const MAX_OFFSET: i64 = 1000;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct PaginationDTO<C = Cursor> {
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<i64>,
+    pub cursor: Option<C>,
+}
+
+impl<C> PaginationDTO<C> {
+    pub fn limit(&self) -> i64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT)
+    }
+}
+
+impl PaginationDTO<OffsetCursor> {
+    pub fn offset(&self) -> i64 {
+        self.cursor.as_ref().map_or(0, |c| c.0)
+    }
+}
+
+impl PaginationDTO {
+    pub fn cursor_created_at(&self) -> Option<DateTime<Utc>> {
+        self.cursor.as_ref().map(|c| c.created_at)
+    }
+
+    pub fn cursor_id(&self) -> Option<i64> {
+        self.cursor.as_ref().map(|c| c.id)
+    }
+}
+
+/// Position of the last returned row in `(created_at, id)` order. Clients get
+/// it as an opaque string and must not rely on its format.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: i64,
+}
+
+impl Cursor {
+    pub fn new(created_at: DateTime<Utc>, id: i64) -> Self {
+        Self { created_at, id }
+    }
+
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}",
+            self.created_at.timestamp_micros(),
+            self.id
+        ))
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(value).ok()?).ok()?;
+        let (micros, id) = decoded.split_once(':')?;
+
+        Some(Self {
+            created_at: DateTime::from_timestamp_micros(micros.parse().ok()?)?,
+            id: id.parse().ok()?,
+        })
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Cursor::decode(&value).ok_or_else(|| de::Error::custom("cursor is invalid"))
+    }
+}
+
+/// Number of rows to skip, for results ordered by relevance. Like [`Cursor`],
+/// it is opaque for clients.
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetCursor(pub i64);
+
+impl OffsetCursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.0.to_string())
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(value).ok()?).ok()?;
+        let offset = decoded.parse().ok()?;
+
+        (0..=MAX_OFFSET).contains(&offset).then_some(Self(offset))
+    }
+}
+
+impl Serialize for OffsetCursor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.encode())
+    }
+}
+
+impl<'de> Deserialize<'de> for OffsetCursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        OffsetCursor::decode(&value).ok_or_else(|| de::Error::custom("cursor is invalid"))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T, C = Cursor> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<C>,
+}
+
+impl<T> Page<T, OffsetCursor> {
+    /// The last page is the one that reaches the maximum offset.
+    pub fn with_offset(items: Vec<T>, limit: i64, offset: i64) -> Self {
+        let mut page = Self::new(items, limit, |_| OffsetCursor(offset + limit));
+        page.next_cursor = page.next_cursor.filter(|c| c.0 <= MAX_OFFSET);
+        page
+    }
+}
+
+impl<T, C> Page<T, C> {
+    /// `items` must be fetched with `limit + 1` rows: the extra row only tells
+    /// that there is a next page and is not returned.
+    pub fn new(mut items: Vec<T>, limit: i64, cursor_of: impl Fn(&T) -> C) -> Self {
+        let next_cursor = if items.len() as i64 > limit {
+            items.truncate(limit as usize);
+            items.last().map(cursor_of)
+        } else {
+            None
+        };
+
+        Self { items, next_cursor }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_survives_encoding() {
+        let created_at = DateTime::from_timestamp_micros(1_735_000_000_123_456).unwrap();
+        let cursor = Cursor::decode(&Cursor::new(created_at, 42).encode()).unwrap();
+
+        assert_eq!(cursor.created_at, created_at);
+        assert_eq!(cursor.id, 42);
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        assert!(Cursor::decode("").is_none());
+        assert!(Cursor::decode("not base64!").is_none());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("123")).is_none());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("abc:1")).is_none());
+        assert!(Cursor::decode(&URL_SAFE_NO_PAD.encode("123:abc")).is_none());
+    }
+
+    #[test]
+    fn offset_cursor_is_limited() {
+        assert_eq!(
+            OffsetCursor::decode(&OffsetCursor(40).encode()).unwrap().0,
+            40
+        );
+        assert!(OffsetCursor::decode(&OffsetCursor(MAX_OFFSET + 1).encode()).is_none());
+        assert!(OffsetCursor::decode(&OffsetCursor(-1).encode()).is_none());
+    }
+
+    #[test]
+    fn page_takes_cursor_from_last_returned_item() {
+        let page = Page::new(vec![1, 2, 3], 2, |&n| n);
+        assert_eq!(page.items, [1, 2]);
+        assert_eq!(page.next_cursor, Some(2));
+
+        let page = Page::new(vec![1, 2], 2, |&n| n);
+        assert_eq!(page.items, [1, 2]);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[test]
+    fn offset_page_ends_at_max_offset() {
+        let page = Page::with_offset(vec![1, 2, 3], 2, MAX_OFFSET - 2);
+        assert_eq!(page.next_cursor.map(|c| c.0), Some(MAX_OFFSET));
+
+        let page = Page::with_offset(vec![1, 2, 3], 2, MAX_OFFSET);
+        assert!(page.next_cursor.is_none());
+    }
+}
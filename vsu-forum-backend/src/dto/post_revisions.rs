@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PostRevisionDTO {
+    pub id: i64,
+    pub post_id: i64,
+    pub editor_id: Option<i64>,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostRevisionsDiffQueryDTO {
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostRevisionsDiffDTO {
+    pub from: i64,
+    pub to: i64,
+    pub lines: Vec<DiffLineDTO>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffLineDTO {
+    pub change: DiffChange,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffChange {
+    Equal,
+    Insert,
+    Delete,
+}
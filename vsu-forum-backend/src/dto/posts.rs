@@ -2,22 +2,49 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use super::users::UserDTO;
+use super::{attachments::AttachmentDTO, users::UserDTO};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PostDTO {
     pub id: i64,
     pub topic_id: i64,
+    /// Markdown source of the post.
     pub text: String,
+    /// Sanitised HTML rendered from `text`.
+    pub text_html: String,
     pub sender: UserDTO,
+    pub reply_to_post_id: Option<i64>,
     pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    /// Removed posts are kept as tombstones: their text is replaced with a
+    /// note about who removed them.
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Registered users mentioned in the text as `@login`.
+    pub mentions: Vec<UserDTO>,
+    pub attachments: Vec<AttachmentDTO>,
+}
+
+/// Post in a list of posts from different topics.
+#[derive(Debug, Serialize)]
+pub struct UserPostDTO {
+    #[serde(flatten)]
+    pub post: PostDTO,
+    pub topic_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostTreeNodeDTO {
+    #[serde(flatten)]
+    pub post: PostDTO,
+    pub replies: Vec<PostTreeNodeDTO>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct CreatePostDTO {
     pub topic_id: i64,
-    #[validate(length(min = 1, max = 1000))]
+    #[validate(length(min = 1, max = 5000))]
     pub text: String,
+    pub reply_to_post_id: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -27,6 +54,6 @@ pub struct GetPostsDTO {
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct UpdatePostDTO {
-    #[validate(length(min = 1, max = 1000))]
+    #[validate(length(min = 1, max = 5000))]
     pub text: Option<String>,
 }
@@ -1,21 +1,57 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::models::{ReportStatus, ReportTargetType};
+
+use super::users::UserDTO;
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ReportDTO {
     pub id: i64,
     pub reported_user_name: String,
     pub author_id: i64,
     pub reason: String,
+    pub target_type: ReportTargetType,
+    /// Set for post reports and for topic reports; for a post report it is
+    /// the topic of the post. Becomes null once the content is purged.
+    pub topic_id: Option<i64>,
+    pub post_id: Option<i64>,
+    /// Post text or topic name as it was when the report was created.
+    pub content_snapshot: Option<String>,
+    pub status: ReportStatus,
+    /// Moderator who handles the report.
+    pub assignee: Option<UserDTO>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
 }
 
+/// Exactly one of `reported_user_name`, `post_id` and `topic_id` must be set;
+/// a post or a topic report is filed against the author of the content.
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct CreateReportDTO {
-    pub reported_user_name: String,
+    pub reported_user_name: Option<String>,
+    pub post_id: Option<i64>,
+    pub topic_id: Option<i64>,
+    #[validate(length(min = 1, max = 2000))]
     pub reason: String,
 }
 
+#[derive(Deserialize)]
+pub struct GetReportsDTO {
+    pub status: Option<ReportStatus>,
+}
+
+/// Taking a report in review assigns it to the current moderator unless it
+/// already has an assignee; closing it requires a resolution note.
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct UpdateReportDTO {
+    #[validate(length(min = 1, max = 2000))]
     pub reason: Option<String>,
+    pub status: Option<ReportStatus>,
+    pub assignee_id: Option<i64>,
+    #[validate(length(min = 1, max = 2000))]
+    pub resolution_note: Option<String>,
 }
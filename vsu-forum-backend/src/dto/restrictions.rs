@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::RestrictionKind;
+
+use super::users::UserDTO;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RestrictionDTO {
+    pub id: i64,
+    pub user_id: i64,
+    pub kind: RestrictionKind,
+    pub reason: String,
+    /// Moderator who issued the restriction.
+    pub issued_by: Option<UserDTO>,
+    pub created_at: DateTime<Utc>,
+    /// Null for permanent restrictions.
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct CreateRestrictionDTO {
+    pub kind: RestrictionKind,
+    #[validate(length(min = 1, max = 2000))]
+    pub reason: String,
+    /// Leave empty to restrict the user permanently.
+    pub expires_at: Option<DateTime<Utc>>,
+}
@@ -1,6 +1,66 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use validator::Validate;
 
-#[derive(Debug, Deserialize, Serialize)]
+use super::{
+    pagination::{OffsetCursor, Page},
+    posts::PostDTO,
+    topics::TopicDTO,
+    users::UserDTO,
+};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchResultType {
+    #[default]
+    Topics,
+    Posts,
+    Users,
+}
+
+/// Results are ordered by relevance which changes together with the content,
+/// so unlike the lists they are paginated with an [`OffsetCursor`] taken
+/// from the query together with the limit.
+///
+/// Category, author and date filters are ignored when searching users.
+#[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct SearchQueryParamsDTO {
+    #[validate(length(min = 1, max = 200))]
     pub query: String,
+    #[serde(rename = "type", default)]
+    pub result_type: SearchResultType,
+    pub category_id: Option<i64>,
+    /// Login of the topic or post author.
+    pub author: Option<String>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+}
+
+pub type SearchPage<T> = Page<T, OffsetCursor>;
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SearchResultsDTO {
+    Topics(SearchPage<TopicSearchHitDTO>),
+    Posts(SearchPage<PostSearchHitDTO>),
+    Users(SearchPage<UserDTO>),
+}
+
+/// `headline` is an HTML fragment of the matched text where the found words
+/// are wrapped in `<mark>` tags.
+#[derive(Debug, Serialize)]
+pub struct TopicSearchHitDTO {
+    #[serde(flatten)]
+    pub topic: TopicDTO,
+    pub headline: String,
+    pub rank: f32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostSearchHitDTO {
+    #[serde(flatten)]
+    pub post: PostDTO,
+    pub topic_name: String,
+    pub headline: String,
+    pub rank: f32,
 }
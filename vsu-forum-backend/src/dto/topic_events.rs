@@ -0,0 +1,46 @@
+use serde::Serialize;
+
+use super::posts::PostDTO;
+
+/// Change in a topic pushed to its subscribers. Removed posts are sent as
+/// tombstones, the same way `get_posts` returns them.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TopicEventDTO {
+    PostCreated {
+        post: PostDTO,
+    },
+    PostEdited {
+        post: PostDTO,
+    },
+    PostRemoved {
+        post: PostDTO,
+    },
+    PostRestored {
+        post: PostDTO,
+    },
+    ReactionAdded {
+        post_id: i64,
+        reaction_id: i64,
+        author_id: i64,
+    },
+    ReactionRemoved {
+        post_id: i64,
+        reaction_id: i64,
+        author_id: i64,
+    },
+}
+
+impl TopicEventDTO {
+    /// Name of the server-sent event, equal to `type`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TopicEventDTO::PostCreated { .. } => "post_created",
+            TopicEventDTO::PostEdited { .. } => "post_edited",
+            TopicEventDTO::PostRemoved { .. } => "post_removed",
+            TopicEventDTO::PostRestored { .. } => "post_restored",
+            TopicEventDTO::ReactionAdded { .. } => "reaction_added",
+            TopicEventDTO::ReactionRemoved { .. } => "reaction_removed",
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -7,12 +8,91 @@ pub struct UserDTO {
     pub login: String,
 }
 
+/// Public profile of a user; the counts include only content that is not
+/// removed.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserProfileDTO {
+    pub id: i64,
+    pub login: String,
+    pub display_name: Option<String>,
+    pub bio: Option<String>,
+    /// Path of a PNG avatar relative to the API root; it changes together
+    /// with the avatar, so it can be cached.
+    pub avatar_url: Option<String>,
+    pub registered_at: DateTime<Utc>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub topics_count: i64,
+    pub posts_count: i64,
+    /// Reactions of other users to the posts of this user.
+    pub reactions_received: i64,
+}
+
+/// Omitted fields are left as is, empty strings clear them.
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct UpdateProfileDTO {
+    #[validate(length(max = 64))]
+    pub display_name: Option<String>,
+    #[validate(length(max = 2000))]
+    pub bio: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CurrentUserDTO {
+    pub id: i64,
+    pub login: String,
+    pub unread_notifications: i64,
+    /// Only the user sees their own address.
+    pub email: Option<String>,
+    pub email_verified: bool,
+}
+
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct RegisterDTO {
     #[validate(length(min = 3, max = 40))]
     pub login: String,
     #[validate(length(min = 3, max = 40))]
     pub password: String,
+    /// Needed to reset a forgotten password and, if the forum requires it,
+    /// to post.
+    #[validate(email, length(max = 254))]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ChangeEmailDTO {
+    #[validate(email, length(max = 254))]
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct VerifyEmailDTO {
+    /// Token from the verification mail.
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ChangePasswordDTO {
+    pub old_password: String,
+    #[validate(length(min = 3, max = 40))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct RequestPasswordResetDTO {
+    pub login: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct ResetPasswordDTO {
+    /// Token from the password reset mail.
+    pub token: String,
+    #[validate(length(min = 3, max = 40))]
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct DeleteAccountDTO {
+    pub password: String,
 }
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
@@ -21,8 +101,23 @@ pub struct LoginDTO {
     pub password: String,
 }
 
+/// Sent with failed and locked out logins, so the client can show how many
+/// attempts are left or count down to `locked_until`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LoginAttemptsDTO {
+    pub failed_attempts: i32,
+    pub attempts_left: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AuthorizedUserDTO {
     pub id: i64,
     pub token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct RefreshTokenDTO {
+    pub refresh_token: String,
 }
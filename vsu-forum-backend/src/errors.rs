@@ -1,11 +1,32 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::Utc;
 
-use crate::dto::errors::ErrorDTO;
+use crate::{
+    dto::{
+        errors::{ErrorDTO, ErrorWithDataDTO},
+        restrictions::RestrictionDTO,
+        users::LoginAttemptsDTO,
+    },
+    models::RestrictionKind,
+};
 
 pub enum ApiError {
     InternalServerError,
     BadRequest(String),
     NotFound(String),
+    Forbidden(String),
+    /// The user is banned or muted; the restriction is returned to them
+    /// together with its reason and expiry time.
+    Restricted(RestrictionDTO),
+    /// Wrong login or password.
+    LoginFailed(LoginAttemptsDTO),
+    /// Login attempt while the login or the client address is locked out;
+    /// `locked_until` is always set.
+    LoginLocked(LoginAttemptsDTO),
     OtherError(StatusCode, String),
 }
 
@@ -20,6 +41,56 @@ impl IntoResponse for ApiError {
             ),
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, Json(ErrorDTO { err: msg })),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, Json(ErrorDTO { err: msg })),
+            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, Json(ErrorDTO { err: msg })),
+            ApiError::Restricted(restriction) => {
+                let action = match restriction.kind {
+                    RestrictionKind::Ban => "banned",
+                    RestrictionKind::Mute => "muted",
+                };
+                let err = match restriction.expires_at {
+                    Some(expires_at) => {
+                        format!("you are {action} until {}", expires_at.to_rfc3339())
+                    }
+                    None => format!("you are {action} permanently"),
+                };
+
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(ErrorWithDataDTO {
+                        err,
+                        data: restriction,
+                    }),
+                )
+                    .into_response();
+            }
+            ApiError::LoginFailed(attempts) => {
+                return (
+                    StatusCode::UNAUTHORIZED,
+                    Json(ErrorWithDataDTO {
+                        err: "login or password is incorrect".to_string(),
+                        data: attempts,
+                    }),
+                )
+                    .into_response();
+            }
+            ApiError::LoginLocked(attempts) => {
+                let retry_after_secs = attempts
+                    .locked_until
+                    .map(|locked_until| (locked_until - Utc::now()).num_seconds().max(1))
+                    .unwrap_or(1);
+
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, retry_after_secs.to_string())],
+                    Json(ErrorWithDataDTO {
+                        err: format!(
+                            "too many failed logins, try again in {retry_after_secs} seconds"
+                        ),
+                        data: attempts,
+                    }),
+                )
+                    .into_response();
+            }
             ApiError::OtherError(code, msg) => (code, Json(ErrorDTO { err: msg })),
         }
         .into_response()
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+use crate::dto::topic_events::TopicEventDTO;
+
+/// Events that are not received by a slow subscriber in time are dropped for
+/// it, and it is told to reload the topic.
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct TopicEvent {
+    pub topic_id: i64,
+    pub event: Arc<TopicEventDTO>,
+}
+
+/// In-process hub: every subscriber gets events of all topics and picks the
+/// ones of its topic, so it works only while the forum runs as one instance.
+#[derive(Debug, Clone)]
+pub struct TopicEventsHub {
+    sender: broadcast::Sender<TopicEvent>,
+}
+
+impl TopicEventsHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn publish(&self, topic_id: i64, event: TopicEventDTO) {
+        // fails only when nobody is subscribed, and then nobody needs it
+        let _ = self.sender.send(TopicEvent {
+            topic_id,
+            event: Arc::new(event),
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TopicEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for TopicEventsHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,10 @@
 use async_trait::async_trait;
 use axum::{
-    extract::{rejection::JsonRejection, FromRequest, Json, Request},
-    http::StatusCode,
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        FromRequest, FromRequestParts, Json, Query, Request,
+    },
+    http::{request::Parts, StatusCode},
     response::{IntoResponse, Response},
 };
 use serde::de::DeserializeOwned;
@@ -58,3 +61,51 @@ impl IntoResponse for JsonValidationError {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = QueryValidationError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state).await?;
+        value.validate()?;
+        Ok(ValidatedQuery(value))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum QueryValidationError {
+    #[error(transparent)]
+    ValidationError(#[from] validator::ValidationErrors),
+
+    #[error(transparent)]
+    AxumQueryRejection(#[from] QueryRejection),
+}
+
+impl IntoResponse for QueryValidationError {
+    fn into_response(self) -> Response {
+        match self {
+            QueryValidationError::ValidationError(validation_errors) => {
+                let error = ErrorWithDataDTO {
+                    err: "validation error occurred".to_string(),
+                    data: validation_errors,
+                };
+                (StatusCode::BAD_REQUEST, Json(error)).into_response()
+            }
+            QueryValidationError::AxumQueryRejection(reason) => {
+                let error = ErrorWithDataDTO {
+                    err: "passed query is invalid".to_string(),
+                    data: reason.body_text(),
+                };
+                (StatusCode::BAD_REQUEST, Json(error)).into_response()
+            }
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use anyhow::Context;
+use axum::{extract::State, http::StatusCode, Extension};
+use chrono::{Duration, Utc};
+use lettre::Address;
+
+use crate::{
+    dto::{
+        claims::Claims,
+        users::{
+            ChangeEmailDTO, ChangePasswordDTO, DeleteAccountDTO, RequestPasswordResetDTO,
+            ResetPasswordDTO, VerifyEmailDTO,
+        },
+    },
+    errors::ApiError,
+    extractors::ValidatedJson,
+    mailer::Mail,
+    models::User,
+    state::ApplicationState,
+    tools::{
+        generate_secret_token, hash_password, hash_secret_token, verify_password,
+        PasswordVerification,
+    },
+    uploads::delete_stored_files,
+};
+
+/// Deleted accounts get the `deleted-<id>` login; such logins cannot be
+/// registered.
+pub const DELETED_LOGIN_PREFIX: &str = "deleted-";
+
+/// Other sessions of the user are revoked, the current one stays.
+pub async fn change_password(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+    ValidatedJson(change_password_dto): ValidatedJson<ChangePasswordDTO>,
+) -> Result<StatusCode, ApiError> {
+    let user = find_active_user(&state, claims.user_id).await?;
+    ensure_password_matches(
+        &state,
+        &user,
+        change_password_dto.old_password,
+        "old password",
+    )
+    .await?;
+
+    let password_hash = hash_password(
+        change_password_dto.new_password,
+        state.config.password_hashing.clone(),
+    )
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "update users set password_hash = $1 where id = $2",
+        password_hash,
+        user.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "
+        update sessions set revoked_at = now()
+        where user_id = $1 and id <> $2 and revoked_at is null
+        ",
+        user.id,
+        claims.session_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Responds the same way whether the login exists or not, so it cannot be
+/// used to find out registered logins: the token is created and mailed in the
+/// background. The token is mailed to the verified address, users without one
+/// cannot reset their password. Only the newest token of a user works.
+pub async fn request_password_reset(
+    State(state): State<ApplicationState>,
+    ValidatedJson(request_dto): ValidatedJson<RequestPasswordResetDTO>,
+) -> StatusCode {
+    tokio::spawn(async move {
+        let login = request_dto.login;
+        if let Err(err) = send_password_reset_token(&state, &login).await {
+            log::warn!("cannot send password reset mail for login {login}: {err:#}");
+        }
+    });
+
+    StatusCode::ACCEPTED
+}
+
+async fn send_password_reset_token(state: &ApplicationState, login: &str) -> anyhow::Result<()> {
+    let user = sqlx::query!(
+        r#"
+        select id, login, email as "email!" from users
+        where login = $1 and deleted_at is null and email_verified_at is not null
+        "#,
+        login
+    )
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    let Some(user) = user else {
+        return Ok(());
+    };
+
+    let to = user
+        .email
+        .parse()
+        .context("stored e-mail address is invalid")?;
+    let token = generate_secret_token();
+    let expires_at = Utc::now() + Duration::minutes(state.config.password_reset.token_ttl_minutes);
+
+    let mut tx = state.db_pool.begin().await?;
+
+    sqlx::query!(
+        "delete from password_reset_tokens where user_id = $1 and used_at is null",
+        user.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "insert into password_reset_tokens(user_id, token_hash, expires_at) values ($1, $2, $3)",
+        user.id,
+        hash_secret_token(&token),
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let mail = Mail {
+        to,
+        subject: "Password reset".to_string(),
+        body: format!(
+            "Somebody requested a password reset for the account {}.\n\
+             If it was you, use this token to set a new password: {token}\n\
+             The token expires at {}. If it was not you, ignore this mail.",
+            user.login,
+            expires_at.to_rfc3339()
+        ),
+    };
+    state.mailer.send(&mail).await
+}
+
+/// All sessions of the user are revoked, so whoever knew the old password is
+/// logged out.
+pub async fn reset_password(
+    State(state): State<ApplicationState>,
+    ValidatedJson(reset_dto): ValidatedJson<ResetPasswordDTO>,
+) -> Result<StatusCode, ApiError> {
+    let password_hash = hash_password(
+        reset_dto.new_password,
+        state.config.password_hashing.clone(),
+    )
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let user_id = sqlx::query_scalar!(
+        "
+        update password_reset_tokens set used_at = now()
+        where token_hash = $1 and used_at is null and expires_at > now()
+        returning user_id
+        ",
+        hash_secret_token(&reset_dto.token)
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::BadRequest("reset token is invalid or expired".to_string()))?;
+
+    sqlx::query!(
+        "update users set password_hash = $1 where id = $2",
+        password_hash,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "update sessions set revoked_at = now() where user_id = $1 and revoked_at is null",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Topics, posts and reactions of the user stay, but the account is
+/// anonymised: the login is replaced, the profile and private data are
+/// deleted and nobody can log in to it anymore.
+pub async fn delete_account(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+    ValidatedJson(delete_account_dto): ValidatedJson<DeleteAccountDTO>,
+) -> Result<StatusCode, ApiError> {
+    let user = find_active_user(&state, claims.user_id).await?;
+    ensure_password_matches(&state, &user, delete_account_dto.password, "password").await?;
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "
+        update users set
+            login = $2 || id,
+            password_hash = '',
+            display_name = null,
+            bio = null,
+            avatar_key = null,
+            last_login_ip = null,
+            email = null,
+            email_verified_at = null,
+            deleted_at = now()
+        where id = $1
+        ",
+        user.id,
+        DELETED_LOGIN_PREFIX
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "update sessions set revoked_at = now() where user_id = $1 and revoked_at is null",
+        user.id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "
+        with
+            reset_tokens as (delete from password_reset_tokens where user_id = $1),
+            verification_tokens as (delete from email_verification_tokens where user_id = $1),
+            bookmarks as (delete from bookmarks where user_id = $1),
+            notifications as (delete from notifications where user_id = $1),
+            mentions as (delete from post_mentions where user_id = $1)
+        delete from login_failures where scope = 'login' and key = $2
+        ",
+        user.id,
+        user.login
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    delete_stored_files(state.storage.as_ref(), user.avatar_key).await;
+
+    Ok(StatusCode::OK)
+}
+
+/// Replaces the address with an unverified one and mails a token that
+/// confirms it. Setting the same address again resends the token.
+pub async fn change_email(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+    ValidatedJson(change_email_dto): ValidatedJson<ChangeEmailDTO>,
+) -> Result<StatusCode, ApiError> {
+    let user = find_active_user(&state, claims.user_id).await?;
+    let email = change_email_dto.email.trim().to_string();
+
+    if user.email_verified_at.is_some()
+        && user
+            .email
+            .as_deref()
+            .is_some_and(|e| e.eq_ignore_ascii_case(&email))
+    {
+        return Err(ApiError::BadRequest(
+            "this e-mail is already verified".to_string(),
+        ));
+    }
+    ensure_email_usable(&state, &email).await?;
+
+    sqlx::query!(
+        "update users set email = $1, email_verified_at = null where id = $2",
+        email,
+        user.id
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    send_verification_mail(&state, user.id, &user.login, &email)
+        .await
+        .map_err(|err| {
+            log::warn!("cannot send verification mail to user {}: {err}", user.id);
+            ApiError::InternalServerError
+        })?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// The token confirms only the address it was sent to, so it is useless once
+/// the user changes the address again.
+pub async fn verify_email(
+    State(state): State<ApplicationState>,
+    ValidatedJson(verify_dto): ValidatedJson<VerifyEmailDTO>,
+) -> Result<StatusCode, ApiError> {
+    let invalid_token =
+        || ApiError::BadRequest("verification token is invalid or expired".to_string());
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let token = sqlx::query!(
+        "
+        update email_verification_tokens set used_at = now()
+        where token_hash = $1 and used_at is null and expires_at > now()
+        returning user_id, email
+        ",
+        hash_secret_token(&verify_dto.token)
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(invalid_token)?;
+
+    let verified = sqlx::query!(
+        "
+        update users set email_verified_at = now()
+        where id = $1 and email = $2 and deleted_at is null
+        ",
+        token.user_id,
+        token.email
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|err| match err {
+        sqlx::Error::Database(err) if err.is_unique_violation() => {
+            ApiError::BadRequest("this e-mail is already used by another user".to_string())
+        }
+        _ => ApiError::InternalServerError,
+    })?
+    .rows_affected()
+        > 0;
+
+    if !verified {
+        return Err(invalid_token());
+    }
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Fails if mails cannot be sent to the address or another account has
+/// already verified it.
+pub async fn ensure_email_usable(state: &ApplicationState, email: &str) -> Result<(), ApiError> {
+    if email.parse::<Address>().is_err() {
+        return Err(ApiError::BadRequest(
+            "e-mail address is invalid".to_string(),
+        ));
+    }
+
+    let taken = sqlx::query_scalar!(
+        r#"
+        select exists (
+            select 1 from users where lower(email) = lower($1) and email_verified_at is not null
+        ) as "taken!"
+        "#,
+        email
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if taken {
+        Err(ApiError::BadRequest(
+            "this e-mail is already used by another user".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Only the newest verification token of a user works.
+pub async fn send_verification_mail(
+    state: &ApplicationState,
+    user_id: i64,
+    login: &str,
+    email: &str,
+) -> anyhow::Result<()> {
+    let token = generate_secret_token();
+    let expires_at =
+        Utc::now() + Duration::minutes(state.config.email_verification.token_ttl_minutes);
+
+    let mut tx = state.db_pool.begin().await?;
+
+    sqlx::query!(
+        "delete from email_verification_tokens where user_id = $1 and used_at is null",
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "
+        insert into email_verification_tokens(user_id, email, token_hash, expires_at)
+        values ($1, $2, $3, $4)
+        ",
+        user_id,
+        email,
+        hash_secret_token(&token),
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let mail = Mail {
+        to: email.parse()?,
+        subject: "E-mail verification".to_string(),
+        body: format!(
+            "This address was given for the account {login}.\n\
+             To confirm it, use this token: {token}\n\
+             The token expires at {}. If it was not you, ignore this mail.",
+            expires_at.to_rfc3339()
+        ),
+    };
+    state.mailer.send(&mail).await
+}
+
+async fn find_active_user(state: &ApplicationState, id: i64) -> Result<User, ApiError> {
+    sqlx::query_as!(
+        User,
+        "select * from users where id = $1 and deleted_at is null",
+        id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("user not found".to_string()))
+}
+
+/// `what` names the checked password in the error message.
+async fn ensure_password_matches(
+    state: &ApplicationState,
+    user: &User,
+    password: String,
+    what: &str,
+) -> Result<(), ApiError> {
+    let verification = verify_password(
+        password,
+        user.password_hash.clone(),
+        state.config.password_hashing.clone(),
+    )
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    match verification {
+        PasswordVerification::Invalid => Err(ApiError::BadRequest(format!("{what} is incorrect"))),
+        PasswordVerification::Valid | PasswordVerification::ValidNeedsRehash => Ok(()),
+    }
+}
@@ -0,0 +1,302 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::Response,
+    Extension, Json,
+};
+
+use crate::{
+    audit::record_audit_entry,
+    dto::{attachments::AttachmentDTO, claims::Claims, topic_events::TopicEventDTO},
+    errors::ApiError,
+    handlers::posts::publish_post_event,
+    models::{Attachment, AuditAction},
+    policies::{ensure_can_modify, ensure_can_post},
+    state::ApplicationState,
+    uploads::{delete_stored_files, read_upload, store_upload, stored_file_response},
+};
+
+/// Name of the multipart field with files; there may be several of them.
+const FILE_FIELD: &str = "file";
+
+pub async fn upload_attachments(
+    Path(post_id): Path<i64>,
+    State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Vec<AttachmentDTO>>), ApiError> {
+    ensure_can_post(&state, &claims).await?;
+
+    let author_id = sqlx::query_scalar!(
+        "
+        select p.author_id from posts p
+        join topics t on p.topic_id = t.id
+        where p.id = $1 and p.deleted_at is null and t.deleted_at is null
+        ",
+        post_id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("post with such id not found".to_string()))?;
+
+    if author_id != claims.user_id {
+        return Err(ApiError::Forbidden(
+            "you can attach files only to your own posts".to_string(),
+        ));
+    }
+
+    let config = &state.config.attachments;
+    let mut uploads = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| ApiError::BadRequest(err.body_text()))?
+    {
+        if field.name() != Some(FILE_FIELD) {
+            continue;
+        }
+        if uploads.len() as i64 >= config.max_files_per_post {
+            return Err(too_many_files(config.max_files_per_post));
+        }
+
+        uploads.push(read_upload(field, config).await?);
+    }
+
+    if uploads.is_empty() {
+        return Err(ApiError::BadRequest(format!(
+            "no files in the \"{FILE_FIELD}\" field"
+        )));
+    }
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    // the post is locked, so concurrent uploads cannot exceed the limit together
+    sqlx::query!("select id from posts where id = $1 for update", post_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let attachments_count = sqlx::query_scalar!(
+        r#"select count(*) as "count!" from attachments where post_id = $1"#,
+        post_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if attachments_count + uploads.len() as i64 > config.max_files_per_post {
+        return Err(too_many_files(config.max_files_per_post));
+    }
+
+    let mut stored_keys = Vec::new();
+    let result: Result<Vec<Attachment>, ApiError> = async {
+        let mut attachments = Vec::with_capacity(uploads.len());
+        for (file_name, upload) in &uploads {
+            let stored = store_upload(state.storage.as_ref(), upload)
+                .await
+                .map_err(|err| {
+                    log::warn!("cannot store an uploaded file: {err}");
+                    ApiError::InternalServerError
+                })?;
+            stored_keys.push(stored.storage_key.clone());
+            stored_keys.extend(stored.thumbnail_key.clone());
+
+            let attachment = sqlx::query_as!(
+                Attachment,
+                "
+                insert into attachments(
+                    post_id, uploader_id, file_name, content_type, size_bytes,
+                    width, height, storage_key, thumbnail_key
+                )
+                values ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                returning *
+                ",
+                post_id,
+                claims.user_id,
+                file_name,
+                upload.content_type,
+                upload.data.len() as i64,
+                upload.image.as_ref().map(|image| image.width as i32),
+                upload.image.as_ref().map(|image| image.height as i32),
+                stored.storage_key,
+                stored.thumbnail_key
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|_| ApiError::InternalServerError)?;
+
+            attachments.push(attachment);
+        }
+
+        Ok(attachments)
+    }
+    .await;
+
+    let attachments = match result {
+        Ok(attachments) => match tx.commit().await {
+            Ok(()) => attachments,
+            Err(_) => {
+                delete_stored_files(state.storage.as_ref(), stored_keys).await;
+                return Err(ApiError::InternalServerError);
+            }
+        },
+        Err(err) => {
+            delete_stored_files(state.storage.as_ref(), stored_keys).await;
+            return Err(err);
+        }
+    };
+
+    publish_post_event(&state, post_id, |post| TopicEventDTO::PostEdited { post }).await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(attachments.into_iter().map(attachment_to_dto).collect()),
+    ))
+}
+
+pub async fn get_attachment(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<Response, ApiError> {
+    serve_attachment(&state, id, false).await
+}
+
+pub async fn get_attachment_thumbnail(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<Response, ApiError> {
+    serve_attachment(&state, id, true).await
+}
+
+pub async fn remove_attachment(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, ApiError> {
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let attachment = sqlx::query_as!(
+        Attachment,
+        "select * from attachments where id = $1 for update",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("attachment with such id not found".to_string()))?;
+
+    let author_id = sqlx::query_scalar!(
+        "select author_id from posts where id = $1 and deleted_at is null",
+        attachment.post_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("attachment with such id not found".to_string()))?;
+
+    ensure_can_modify(&claims, author_id)?;
+
+    sqlx::query!("delete from attachments where id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    record_audit_entry(
+        &mut tx,
+        claims.user_id,
+        AuditAction::RemoveAttachment,
+        id,
+        Some(&attachment),
+        None,
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    delete_stored_files(
+        state.storage.as_ref(),
+        [attachment.storage_key]
+            .into_iter()
+            .chain(attachment.thumbnail_key),
+    )
+    .await;
+
+    publish_post_event(&state, attachment.post_id, |post| {
+        TopicEventDTO::PostEdited { post }
+    })
+    .await;
+
+    Ok(StatusCode::OK)
+}
+
+async fn serve_attachment(
+    state: &ApplicationState,
+    id: i64,
+    thumbnail: bool,
+) -> Result<Response, ApiError> {
+    let attachment = sqlx::query!(
+        "
+        select a.file_name, a.content_type, a.storage_key, a.thumbnail_key
+        from attachments a
+        join posts p on a.post_id = p.id
+        join topics t on p.topic_id = t.id
+        where a.id = $1 and p.deleted_at is null and t.deleted_at is null
+        ",
+        id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("attachment with such id not found".to_string()))?;
+
+    let (key, content_type) = if thumbnail {
+        let key = attachment
+            .thumbnail_key
+            .ok_or_else(|| ApiError::NotFound("attachment has no thumbnail".to_string()))?;
+        (key, "image/png".to_string())
+    } else {
+        (attachment.storage_key, attachment.content_type)
+    };
+
+    stored_file_response(
+        state.storage.as_ref(),
+        &key,
+        content_type,
+        &attachment.file_name,
+    )
+    .await
+}
+
+fn attachment_to_dto(attachment: Attachment) -> AttachmentDTO {
+    AttachmentDTO {
+        id: attachment.id,
+        post_id: attachment.post_id,
+        file_name: attachment.file_name,
+        content_type: attachment.content_type,
+        size_bytes: attachment.size_bytes,
+        width: attachment.width,
+        height: attachment.height,
+        url: AttachmentDTO::url(attachment.id),
+        thumbnail_url: attachment
+            .thumbnail_key
+            .map(|_| AttachmentDTO::thumbnail_url(attachment.id)),
+        created_at: attachment.created_at,
+    }
+}
+
+fn too_many_files(max_files_per_post: i64) -> ApiError {
+    ApiError::BadRequest(format!(
+        "a post can have at most {max_files_per_post} attachments"
+    ))
+}
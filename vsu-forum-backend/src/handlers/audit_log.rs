@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::{
+    dto::{
+        audit_log::{AuditLogEntryDTO, GetAuditLogDTO},
+        pagination::{Cursor, Page, PaginationDTO},
+    },
+    errors::ApiError,
+    extractors::ValidatedQuery,
+    state::ApplicationState,
+};
+
+pub async fn get_audit_log(
+    Query(query): Query<GetAuditLogDTO>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<Page<AuditLogEntryDTO>>), ApiError> {
+    let entries = sqlx::query!(
+        r#"
+        SELECT
+            a.id, a.actor_id, u.login as "actor_login?", a.action, a.target_type, a.target_id,
+            a.before, a.after, a.created_at
+        FROM audit_log a
+        LEFT JOIN users u ON a.actor_id = u.id
+        WHERE
+            ($1::bigint IS NULL OR a.actor_id = $1)
+            AND ($2::text IS NULL OR a.action = $2)
+            AND ($3::text IS NULL OR a.target_type = $3)
+            AND ($4::bigint IS NULL OR a.target_id = $4)
+            AND ($5::timestamptz IS NULL OR a.created_at >= $5)
+            AND ($6::timestamptz IS NULL OR a.created_at < $6)
+            AND ($7::timestamptz IS NULL OR (a.created_at, a.id) < ($7::timestamptz, $8::bigint))
+        ORDER BY a.created_at DESC, a.id DESC
+        LIMIT $9
+        "#,
+        query.actor_id,
+        query.action,
+        query.target_type,
+        query.target_id,
+        query.created_from,
+        query.created_to,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|a| AuditLogEntryDTO {
+        id: a.id,
+        actor_id: a.actor_id,
+        actor_login: a.actor_login,
+        action: a.action,
+        target_type: a.target_type,
+        target_id: a.target_id,
+        before: a.before,
+        after: a.after,
+        created_at: a.created_at,
+    })
+    .collect();
+
+    let page = Page::new(entries, pagination.limit(), |a| {
+        Cursor::new(a.created_at, a.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
+}
@@ -1,19 +1,21 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
 
 use crate::{
+    audit::record_audit_entry,
     dto::{
         available_reactions::{
             AvailableReactionDTO, CreateAvailableReactionDTO, UpdateAvailableReactionDTO,
         },
+        claims::Claims,
         common::ObjectCreatedDTO,
     },
     errors::ApiError,
     extractors::ValidatedJson,
-    models::AvailableReaction,
+    models::{AuditAction, AvailableReaction},
     state::ApplicationState,
 };
 
@@ -80,9 +82,35 @@ pub async fn patch_available_reaction(
 pub async fn delete_available_reaction(
     Path(id): Path<i64>,
     State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
 ) -> Result<StatusCode, ApiError> {
-    sqlx::query!("DELETE FROM available_reactions WHERE id = $1", id)
-        .execute(&state.db_pool)
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let available_reaction = sqlx::query_as!(
+        AvailableReaction,
+        "DELETE FROM available_reactions WHERE id = $1 RETURNING id, reaction",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("reaction with such id not found".to_string()))?;
+
+    record_audit_entry(
+        &mut tx,
+        claims.user_id,
+        AuditAction::DeleteAvailableReaction,
+        id,
+        Some(&available_reaction),
+        None,
+    )
+    .await?;
+
+    tx.commit()
         .await
         .map_err(|_| ApiError::InternalServerError)?;
 
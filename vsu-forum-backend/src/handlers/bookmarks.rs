@@ -5,20 +5,36 @@ use axum::{
 };
 
 use crate::{
-    dto::{bookmarks::BookmarkDTO, claims::Claims},
+    dto::{
+        bookmarks::BookmarkDTO,
+        claims::Claims,
+        pagination::{Cursor, Page, PaginationDTO},
+    },
     errors::ApiError,
+    extractors::ValidatedQuery,
     models::Bookmark,
     state::ApplicationState,
 };
 
 pub async fn get_bookmarks(
     Extension(claims): Extension<Claims>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
     State(state): State<ApplicationState>,
-) -> Result<(StatusCode, Json<Vec<BookmarkDTO>>), ApiError> {
+) -> Result<(StatusCode, Json<Page<BookmarkDTO>>), ApiError> {
     let bookmarks = sqlx::query_as!(
         Bookmark,
-        "select * from bookmarks where user_id = $1",
-        claims.user_id
+        "
+        select * from bookmarks
+        where
+            user_id = $1
+            and ($2::timestamptz is null or (created_at, topic_id) < ($2::timestamptz, $3::bigint))
+        order by created_at desc, topic_id desc
+        limit $4
+        ",
+        claims.user_id,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
     )
     .fetch_all(&state.db_pool)
     .await
@@ -26,10 +42,15 @@ pub async fn get_bookmarks(
     .iter()
     .map(|p| BookmarkDTO {
         topic_id: p.topic_id,
+        created_at: p.created_at,
     })
     .collect();
 
-    Ok((StatusCode::OK, Json(bookmarks)))
+    let page = Page::new(bookmarks, pagination.limit(), |b| {
+        Cursor::new(b.created_at, b.topic_id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
 }
 
 pub async fn create_bookmark(
@@ -53,16 +74,22 @@ pub async fn create_bookmark(
         ));
     }
 
-    sqlx::query!(
-        "insert into bookmarks(user_id, topic_id) values ($1, $2)",
+    let created_at = sqlx::query_scalar!(
+        "insert into bookmarks(user_id, topic_id) values ($1, $2) returning created_at",
         claims.user_id,
         topic_id,
     )
-    .execute(&state.db_pool)
+    .fetch_one(&state.db_pool)
     .await
     .map_err(|_| ApiError::InternalServerError)?;
 
-    Result::Ok((StatusCode::CREATED, Json(BookmarkDTO { topic_id })))
+    Result::Ok((
+        StatusCode::CREATED,
+        Json(BookmarkDTO {
+            topic_id,
+            created_at,
+        }),
+    ))
 }
 
 pub async fn remove_bookmark(
@@ -1,10 +1,17 @@
+pub mod account;
+pub mod attachments;
+pub mod audit_log;
 pub mod available_reactions;
 pub mod bookmarks;
+pub mod notifications;
+pub mod post_revisions;
 pub mod posts;
 pub mod reactions;
 pub mod reports;
+pub mod restrictions;
 pub mod search;
 pub mod stats;
+pub mod topic_events;
 pub mod topics;
 pub mod topics_categories;
 pub mod users;
@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+
+use crate::{
+    dto::{
+        claims::Claims,
+        notifications::{GetNotificationsDTO, NotificationDTO},
+        pagination::{Cursor, Page, PaginationDTO},
+        users::UserDTO,
+    },
+    errors::ApiError,
+    extractors::ValidatedQuery,
+    state::ApplicationState,
+};
+
+pub async fn get_notifications(
+    Query(query): Query<GetNotificationsDTO>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
+    State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<(StatusCode, Json<Page<NotificationDTO>>), ApiError> {
+    let notifications = sqlx::query!(
+        r#"
+        SELECT
+            n.id, n.kind, n.topic_id, n.post_id, n.created_at, n.read_at,
+            t.name AS topic_name,
+            u.id AS "actor_id?", u.login AS "actor_login?",
+            ar.reaction AS "reaction?"
+        FROM notifications n
+        JOIN topics t ON n.topic_id = t.id
+        JOIN posts p ON n.post_id = p.id
+        LEFT JOIN users u ON n.actor_id = u.id
+        LEFT JOIN available_reactions ar ON n.reaction_id = ar.id
+        WHERE
+            n.user_id = $1
+            AND t.deleted_at IS NULL
+            AND p.deleted_at IS NULL
+            AND (NOT $2 OR n.read_at IS NULL)
+            AND ($3::timestamptz IS NULL OR (n.created_at, n.id) < ($3::timestamptz, $4::bigint))
+        ORDER BY n.created_at DESC, n.id DESC
+        LIMIT $5
+        "#,
+        claims.user_id,
+        query.unread_only,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|n| {
+        Ok(NotificationDTO {
+            id: n.id,
+            kind: n.kind.parse().map_err(|_| ApiError::InternalServerError)?,
+            actor: n
+                .actor_id
+                .zip(n.actor_login)
+                .map(|(id, login)| UserDTO { id, login }),
+            topic_id: n.topic_id,
+            topic_name: n.topic_name,
+            post_id: n.post_id,
+            reaction: n.reaction,
+            created_at: n.created_at,
+            read_at: n.read_at,
+        })
+    })
+    .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let page = Page::new(notifications, pagination.limit(), |n| {
+        Cursor::new(n.created_at, n.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
+}
+
+pub async fn read_notification(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, ApiError> {
+    let rows_affected = sqlx::query!(
+        "update notifications set read_at = coalesce(read_at, now()) where id = $1 and user_id = $2",
+        id,
+        claims.user_id
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .rows_affected();
+
+    if rows_affected > 0 {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound(
+            "notification with such id not found".to_string(),
+        ))
+    }
+}
+
+pub async fn read_all_notifications(
+    State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, ApiError> {
+    sqlx::query!(
+        "update notifications set read_at = now() where user_id = $1 and read_at is null",
+        claims.user_id
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(StatusCode::OK)
+}
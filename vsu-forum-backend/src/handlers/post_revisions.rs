@@ -0,0 +1,86 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use similar::{ChangeTag, TextDiff};
+
+use crate::{
+    dto::post_revisions::{
+        DiffChange, DiffLineDTO, PostRevisionDTO, PostRevisionsDiffDTO, PostRevisionsDiffQueryDTO,
+    },
+    errors::ApiError,
+    models::PostRevision,
+    state::ApplicationState,
+};
+
+pub async fn get_post_revisions(
+    Path(post_id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<Vec<PostRevisionDTO>>), ApiError> {
+    let revisions = sqlx::query_as!(
+        PostRevision,
+        "select * from post_revisions where post_id = $1 order by created_at, id",
+        post_id
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|r| PostRevisionDTO {
+        id: r.id,
+        post_id: r.post_id,
+        editor_id: r.editor_id,
+        text: r.text,
+        created_at: r.created_at,
+    })
+    .collect();
+
+    Ok((StatusCode::OK, Json(revisions)))
+}
+
+pub async fn get_post_revisions_diff(
+    Path(post_id): Path<i64>,
+    Query(query): Query<PostRevisionsDiffQueryDTO>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<PostRevisionsDiffDTO>), ApiError> {
+    let from_text = get_revision_text(&state, post_id, query.from).await?;
+    let to_text = get_revision_text(&state, post_id, query.to).await?;
+
+    let lines = TextDiff::from_lines(&from_text, &to_text)
+        .iter_all_changes()
+        .map(|change| DiffLineDTO {
+            change: match change.tag() {
+                ChangeTag::Equal => DiffChange::Equal,
+                ChangeTag::Insert => DiffChange::Insert,
+                ChangeTag::Delete => DiffChange::Delete,
+            },
+            text: change.value().trim_end_matches('\n').to_string(),
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(PostRevisionsDiffDTO {
+            from: query.from,
+            to: query.to,
+            lines,
+        }),
+    ))
+}
+
+async fn get_revision_text(
+    state: &ApplicationState,
+    post_id: i64,
+    revision_id: i64,
+) -> Result<String, ApiError> {
+    sqlx::query_scalar!(
+        "select text from post_revisions where id = $1 and post_id = $2",
+        revision_id,
+        post_id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound(format!("revision {revision_id} not found")))
+}
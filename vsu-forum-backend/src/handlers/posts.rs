@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
@@ -5,38 +7,66 @@ use axum::{
 };
 
 use crate::{
+    attachments::attach_post_attachments,
+    audit::record_audit_entry,
     dto::{
         claims::Claims,
         common::ObjectCreatedDTO,
-        posts::{CreatePostDTO, GetPostsDTO, PostDTO, UpdatePostDTO},
+        pagination::{Cursor, Page, PaginationDTO},
+        posts::{CreatePostDTO, GetPostsDTO, PostDTO, PostTreeNodeDTO, UpdatePostDTO, UserPostDTO},
+        topic_events::TopicEventDTO,
         users::UserDTO,
     },
     errors::ApiError,
-    extractors::ValidatedJson,
+    extractors::{ValidatedJson, ValidatedQuery},
+    handlers::users::ensure_user_exists,
+    mentions::{attach_mentions, store_post_mentions},
+    models::{AuditAction, Post},
+    notifications::{notify_about_mentions, notify_about_post},
+    policies::{ensure_can_modify, ensure_can_post},
     state::ApplicationState,
+    tools::render_markdown,
 };
 
 pub async fn get_posts(
     Query(query): Query<GetPostsDTO>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
     State(state): State<ApplicationState>,
-) -> Result<(StatusCode, Json<Vec<PostDTO>>), ApiError> {
-    let posts = sqlx::query!(
-        "
+) -> Result<(StatusCode, Json<Page<PostDTO>>), ApiError> {
+    let mut posts: Vec<PostDTO> = sqlx::query!(
+        r#"
         SELECT
             p.id AS post_id,
             p.topic_id AS topic_id,
             p.created_at as created_at,
-            p.text AS post_text,
+            p.edited_at,
+            CASE
+                WHEN p.deleted_at IS NULL THEN p.text
+                WHEN p.deleted_by = p.author_id THEN 'post removed by author'
+                ELSE 'post removed by moderator'
+            END AS "post_text!",
+            p.deleted_at,
+            p.reply_to_post_id,
             u.id AS sender_id,
             u.login AS sender_login
         FROM
             posts p
         JOIN
             users u ON p.author_id = u.id
+        JOIN
+            topics t ON p.topic_id = t.id
         WHERE
-            p.topic_id = $1;
-        ",
-        query.topic_id
+            t.deleted_at IS NULL
+            AND p.topic_id = $1
+            AND ($2::timestamptz IS NULL OR (p.created_at, p.id) > ($2::timestamptz, $3::bigint))
+        ORDER BY
+            p.created_at, p.id
+        LIMIT $4;
+        "#,
+        query.topic_id,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
     )
     .fetch_all(&state.db_pool)
     .await
@@ -45,60 +75,295 @@ pub async fn get_posts(
     .map(|record| PostDTO {
         id: record.post_id,
         created_at: record.created_at,
+        edited_at: record.edited_at,
         sender: UserDTO {
             id: record.sender_id,
             login: record.sender_login.clone(),
         },
         topic_id: record.topic_id,
+        text_html: render_markdown(&record.post_text),
         text: record.post_text.clone(),
+        reply_to_post_id: record.reply_to_post_id,
+        deleted_at: record.deleted_at,
+        mentions: Vec::new(),
+        attachments: Vec::new(),
     })
     .collect();
 
-    Ok((StatusCode::OK, Json(posts)))
+    attach_mentions(&state.db_pool, &mut posts)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+    attach_post_attachments(&state.db_pool, &mut posts)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let page = Page::new(posts, pagination.limit(), |p| {
+        Cursor::new(p.created_at, p.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
 }
 
 pub async fn get_post(
     Path(id): Path<i64>,
     State(state): State<ApplicationState>,
 ) -> Result<(StatusCode, Json<PostDTO>), ApiError> {
-    let record = sqlx::query!(
-        "
+    match find_post(&state, id).await? {
+        Some(post_dto) => Ok((StatusCode::OK, Json(post_dto))),
+        None => Err(ApiError::NotFound("post not found".to_string())),
+    }
+}
+
+/// Posts written by the user, newest first.
+pub async fn get_user_posts(
+    Path(user_id): Path<i64>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<Page<UserPostDTO>>), ApiError> {
+    ensure_user_exists(&state, user_id).await?;
+
+    let mut posts: Vec<UserPostDTO> = sqlx::query!(
+        r#"
         SELECT
-        p.id AS post_id,
-        p.topic_id AS topic_id,
-        p.created_at as created_at,
-        p.text AS post_text,
-        u.id AS sender_id,
-        u.login AS sender_login
+            p.id AS post_id,
+            p.topic_id AS topic_id,
+            p.created_at as created_at,
+            p.edited_at,
+            p.text AS post_text,
+            p.reply_to_post_id,
+            u.id AS sender_id,
+            u.login AS sender_login,
+            t.name AS topic_name
         FROM
             posts p
         JOIN
             users u ON p.author_id = u.id
+        JOIN
+            topics t ON p.topic_id = t.id
         WHERE
-            p.id = $1;
-        ",
-        id
+            p.author_id = $1
+            AND p.deleted_at IS NULL
+            AND t.deleted_at IS NULL
+            AND ($2::timestamptz IS NULL OR (p.created_at, p.id) < ($2::timestamptz, $3::bigint))
+        ORDER BY
+            p.created_at DESC, p.id DESC
+        LIMIT $4;
+        "#,
+        user_id,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
     )
-    .fetch_optional(&state.db_pool)
+    .fetch_all(&state.db_pool)
     .await
-    .map_err(|_| ApiError::InternalServerError)?;
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|record| UserPostDTO {
+        post: PostDTO {
+            id: record.post_id,
+            created_at: record.created_at,
+            edited_at: record.edited_at,
+            sender: UserDTO {
+                id: record.sender_id,
+                login: record.sender_login,
+            },
+            topic_id: record.topic_id,
+            text_html: render_markdown(&record.post_text),
+            text: record.post_text,
+            reply_to_post_id: record.reply_to_post_id,
+            deleted_at: None,
+            mentions: Vec::new(),
+            attachments: Vec::new(),
+        },
+        topic_name: record.topic_name,
+    })
+    .collect();
 
-    match record {
-        Some(record) => {
-            let post_dto = PostDTO {
-                id: record.post_id,
-                created_at: record.created_at,
-                sender: UserDTO {
-                    id: record.sender_id,
-                    login: record.sender_login.clone(),
-                },
-                topic_id: record.topic_id,
-                text: record.post_text.clone(),
-            };
-            Ok((StatusCode::OK, Json(post_dto)))
-        }
-        None => Err(ApiError::NotFound("post not found".to_string())),
-    }
+    attach_mentions(&state.db_pool, posts.iter_mut().map(|p| &mut p.post))
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+    attach_post_attachments(&state.db_pool, posts.iter_mut().map(|p| &mut p.post))
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let page = Page::new(posts, pagination.limit(), |p| {
+        Cursor::new(p.post.created_at, p.post.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
+}
+
+/// Posts that mention the user, newest first.
+pub async fn get_user_mentions(
+    Path(user_id): Path<i64>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<Page<PostDTO>>), ApiError> {
+    ensure_user_exists(&state, user_id).await?;
+
+    let mut posts: Vec<PostDTO> = sqlx::query!(
+        r#"
+        SELECT
+            p.id AS post_id,
+            p.topic_id AS topic_id,
+            p.created_at as created_at,
+            p.edited_at,
+            p.text AS post_text,
+            p.reply_to_post_id,
+            u.id AS sender_id,
+            u.login AS sender_login
+        FROM
+            post_mentions pm
+        JOIN
+            posts p ON pm.post_id = p.id
+        JOIN
+            users u ON p.author_id = u.id
+        JOIN
+            topics t ON p.topic_id = t.id
+        WHERE
+            pm.user_id = $1
+            AND p.deleted_at IS NULL
+            AND t.deleted_at IS NULL
+            AND ($2::timestamptz IS NULL OR (p.created_at, p.id) < ($2::timestamptz, $3::bigint))
+        ORDER BY
+            p.created_at DESC, p.id DESC
+        LIMIT $4;
+        "#,
+        user_id,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|record| PostDTO {
+        id: record.post_id,
+        created_at: record.created_at,
+        edited_at: record.edited_at,
+        sender: UserDTO {
+            id: record.sender_id,
+            login: record.sender_login,
+        },
+        topic_id: record.topic_id,
+        text_html: render_markdown(&record.post_text),
+        text: record.post_text,
+        reply_to_post_id: record.reply_to_post_id,
+        deleted_at: None,
+        mentions: Vec::new(),
+        attachments: Vec::new(),
+    })
+    .collect();
+
+    attach_mentions(&state.db_pool, &mut posts)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+    attach_post_attachments(&state.db_pool, &mut posts)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let page = Page::new(posts, pagination.limit(), |p| {
+        Cursor::new(p.created_at, p.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
+}
+
+/// Threads of the topic, paginated by their root posts; every page has the
+/// roots with all their replies.
+pub async fn get_posts_tree(
+    Path(topic_id): Path<i64>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<Page<PostTreeNodeDTO>>), ApiError> {
+    let mut posts: Vec<PostDTO> = sqlx::query!(
+        r#"
+        WITH RECURSIVE roots AS (
+            SELECT p.id
+            FROM posts p
+            JOIN topics t ON p.topic_id = t.id
+            WHERE
+                t.deleted_at IS NULL
+                AND p.topic_id = $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM posts parent
+                    WHERE parent.id = p.reply_to_post_id AND parent.topic_id = p.topic_id
+                )
+                AND ($2::timestamptz IS NULL OR (p.created_at, p.id) > ($2::timestamptz, $3::bigint))
+            ORDER BY p.created_at, p.id
+            LIMIT $4
+        ),
+        thread AS (
+            SELECT id FROM roots
+            UNION ALL
+            SELECT p.id
+            FROM posts p
+            JOIN thread ON p.reply_to_post_id = thread.id
+            WHERE p.topic_id = $1
+        )
+        SELECT
+            p.id AS post_id,
+            p.topic_id AS topic_id,
+            p.created_at as created_at,
+            p.edited_at,
+            CASE
+                WHEN p.deleted_at IS NULL THEN p.text
+                WHEN p.deleted_by = p.author_id THEN 'post removed by author'
+                ELSE 'post removed by moderator'
+            END AS "post_text!",
+            p.deleted_at,
+            p.reply_to_post_id,
+            u.id AS sender_id,
+            u.login AS sender_login
+        FROM
+            posts p
+        JOIN
+            thread ON p.id = thread.id
+        JOIN
+            users u ON p.author_id = u.id
+        ORDER BY
+            p.created_at, p.id;
+        "#,
+        topic_id,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|record| PostDTO {
+        id: record.post_id,
+        created_at: record.created_at,
+        edited_at: record.edited_at,
+        sender: UserDTO {
+            id: record.sender_id,
+            login: record.sender_login,
+        },
+        topic_id: record.topic_id,
+        text_html: render_markdown(&record.post_text),
+        text: record.post_text,
+        reply_to_post_id: record.reply_to_post_id,
+        deleted_at: record.deleted_at,
+        mentions: Vec::new(),
+        attachments: Vec::new(),
+    })
+    .collect();
+
+    attach_mentions(&state.db_pool, &mut posts)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+    attach_post_attachments(&state.db_pool, &mut posts)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let page = Page::new(build_posts_tree(posts), pagination.limit(), |node| {
+        Cursor::new(node.post.created_at, node.post.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
 }
 
 pub async fn create_post(
@@ -106,34 +371,151 @@ pub async fn create_post(
     Extension(claims): Extension<Claims>,
     ValidatedJson(create_post_dto): ValidatedJson<CreatePostDTO>,
 ) -> Result<(StatusCode, Json<ObjectCreatedDTO>), ApiError> {
+    ensure_can_post(&state, &claims).await?;
+
+    let topic_exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM topics WHERE id = $1 AND deleted_at IS NULL) AS "exists!""#,
+        create_post_dto.topic_id
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if !topic_exists {
+        return Err(ApiError::NotFound("topic not found".to_string()));
+    }
+
+    if let Some(reply_to_post_id) = create_post_dto.reply_to_post_id {
+        let parent_topic_id = sqlx::query_scalar!(
+            "select topic_id from posts where id = $1 and deleted_at is null",
+            reply_to_post_id
+        )
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+        .ok_or_else(|| ApiError::NotFound("replied post not found".to_string()))?;
+
+        if parent_topic_id != create_post_dto.topic_id {
+            return Err(ApiError::BadRequest(
+                "replied post belongs to another topic".to_string(),
+            ));
+        }
+    }
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
     let result = sqlx::query_scalar!(
-        "insert into posts(author_id, topic_id, text) values ($1, $2, $3) returning id",
+        "insert into posts(author_id, topic_id, text, reply_to_post_id) values ($1, $2, $3, $4) returning id",
         claims.user_id,
         create_post_dto.topic_id,
-        create_post_dto.text
+        create_post_dto.text,
+        create_post_dto.reply_to_post_id
     )
-    .fetch_one(&state.db_pool)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|_| ApiError::InternalServerError)?;
 
+    store_post_mentions(&mut tx, result, &create_post_dto.text)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    publish_post_event(&state, result, |post| TopicEventDTO::PostCreated { post }).await;
+
+    if let Err(err) = notify_about_post(&state.db_pool, result).await {
+        log::warn!("cannot create notifications about post {result}: {err}");
+    }
+
     Result::Ok((StatusCode::CREATED, Json(ObjectCreatedDTO { id: result })))
 }
 
 pub async fn remove_post(
     Path(id): Path<i64>,
     State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
 ) -> Result<StatusCode, ApiError> {
-    let rows_affected = sqlx::query!("delete from posts where id = $1", id)
-        .execute(&state.db_pool)
+    let mut tx = state
+        .db_pool
+        .begin()
         .await
-        .map_err(|_| ApiError::InternalServerError)?
-        .rows_affected();
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let post = sqlx::query_as!(
+        Post,
+        "
+        select id, topic_id, author_id, text, reply_to_post_id, created_at, edited_at, deleted_at, deleted_by
+        from posts
+        where id = $1 and deleted_at is null
+        for update
+        ",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("post with such id not found".to_string()))?;
+
+    ensure_can_modify(&claims, post.author_id)?;
+
+    let removed_post = sqlx::query_as!(
+        Post,
+        "
+        update posts set deleted_at = now(), deleted_by = $2
+        where id = $1
+        returning id, topic_id, author_id, text, reply_to_post_id, created_at, edited_at, deleted_at, deleted_by
+        ",
+        id,
+        claims.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    record_audit_entry(
+        &mut tx,
+        claims.user_id,
+        AuditAction::RemovePost,
+        id,
+        Some(&post),
+        Some(&removed_post),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    publish_post_event(&state, id, |post| TopicEventDTO::PostRemoved { post }).await;
+
+    Result::Ok(StatusCode::OK)
+}
+
+pub async fn restore_post(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<StatusCode, ApiError> {
+    let rows_affected = sqlx::query!(
+        "update posts set deleted_at = null, deleted_by = null where id = $1 and deleted_at is not null",
+        id
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .rows_affected();
 
     if rows_affected > 0 {
+        publish_post_event(&state, id, |post| TopicEventDTO::PostRestored { post }).await;
         Result::Ok(StatusCode::OK)
     } else {
         Err(ApiError::NotFound(
-            "post with such id not found".to_string(),
+            "removed post with such id not found".to_string(),
         ))
     }
 }
@@ -141,19 +523,83 @@ pub async fn remove_post(
 pub async fn patch_post(
     Path(post_id): Path<i64>,
     State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
     ValidatedJson(update_post_dto): ValidatedJson<UpdatePostDTO>,
 ) -> Result<StatusCode, ApiError> {
-    let rows_affected = if let Some(text) = update_post_dto.text {
-        sqlx::query!("update posts set text = $1 where id = $2", text, post_id)
-            .execute(&state.db_pool)
+    ensure_can_post(&state, &claims).await?;
+    let author_id = get_post_author_id(&state, post_id).await?;
+    ensure_can_modify(&claims, author_id)?;
+
+    let (rows_affected, new_mentions) = if let Some(text) = update_post_dto.text {
+        let mut tx = state
+            .db_pool
+            .begin()
             .await
-            .map_err(|_| ApiError::InternalServerError)?
-            .rows_affected()
+            .map_err(|_| ApiError::InternalServerError)?;
+
+        // concurrent edits of the post wait here, so its original version is
+        // saved once
+        sqlx::query!(
+            "select id from posts where id = $1 and deleted_at is null for update",
+            post_id
+        )
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+        .ok_or_else(|| ApiError::NotFound("post with such id not found".to_string()))?;
+
+        // the original version is saved only when the post is edited first time
+        sqlx::query!(
+            "
+            insert into post_revisions(post_id, editor_id, text, created_at)
+            select id, author_id, text, created_at from posts
+            where id = $1 and not exists (select 1 from post_revisions where post_id = $1)
+            ",
+            post_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+        let rows_affected = sqlx::query!(
+            "update posts set text = $1, edited_at = now() where id = $2 and deleted_at is null",
+            text,
+            post_id
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+        .rows_affected();
+
+        sqlx::query!(
+            "insert into post_revisions(post_id, editor_id, text) values ($1, $2, $3)",
+            post_id,
+            claims.user_id,
+            text
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+        let new_mentions = store_post_mentions(&mut tx, post_id, &text)
+            .await
+            .map_err(|_| ApiError::InternalServerError)?;
+
+        tx.commit()
+            .await
+            .map_err(|_| ApiError::InternalServerError)?;
+
+        (rows_affected, new_mentions)
     } else {
-        0
+        (0, Vec::new())
     };
 
     if rows_affected > 0 {
+        publish_post_event(&state, post_id, |post| TopicEventDTO::PostEdited { post }).await;
+
+        if let Err(err) = notify_about_mentions(&state.db_pool, post_id, &new_mentions).await {
+            log::warn!("cannot create notifications about mentions in post {post_id}: {err}");
+        }
         Result::Ok(StatusCode::OK)
     } else {
         Err(ApiError::NotFound(
@@ -161,3 +607,226 @@ pub async fn patch_post(
         ))
     }
 }
+
+async fn find_post(state: &ApplicationState, id: i64) -> Result<Option<PostDTO>, ApiError> {
+    let record = sqlx::query!(
+        r#"
+        SELECT
+        p.id AS post_id,
+        p.topic_id AS topic_id,
+        p.created_at as created_at,
+        p.edited_at,
+        CASE
+            WHEN p.deleted_at IS NULL THEN p.text
+            WHEN p.deleted_by = p.author_id THEN 'post removed by author'
+            ELSE 'post removed by moderator'
+        END AS "post_text!",
+        p.deleted_at,
+        p.reply_to_post_id,
+        u.id AS sender_id,
+        u.login AS sender_login
+        FROM
+            posts p
+        JOIN
+            users u ON p.author_id = u.id
+        JOIN
+            topics t ON p.topic_id = t.id
+        WHERE
+            t.deleted_at IS NULL
+            AND p.id = $1;
+        "#,
+        id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    let Some(record) = record else {
+        return Ok(None);
+    };
+
+    let mut post = PostDTO {
+        id: record.post_id,
+        created_at: record.created_at,
+        edited_at: record.edited_at,
+        sender: UserDTO {
+            id: record.sender_id,
+            login: record.sender_login,
+        },
+        topic_id: record.topic_id,
+        text_html: render_markdown(&record.post_text),
+        text: record.post_text,
+        reply_to_post_id: record.reply_to_post_id,
+        deleted_at: record.deleted_at,
+        mentions: Vec::new(),
+        attachments: Vec::new(),
+    };
+
+    attach_mentions(&state.db_pool, [&mut post])
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+    attach_post_attachments(&state.db_pool, [&mut post])
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(Some(post))
+}
+
+/// Publishing is best effort: the change is already committed, so failing to
+/// load the post must not fail the request.
+pub async fn publish_post_event(
+    state: &ApplicationState,
+    post_id: i64,
+    event: fn(PostDTO) -> TopicEventDTO,
+) {
+    match find_post(state, post_id).await {
+        Ok(Some(post)) => state.topic_events.publish(post.topic_id, event(post)),
+        Ok(None) => {}
+        Err(_) => log::warn!("cannot load post {post_id} to publish a topic event"),
+    }
+}
+
+async fn get_post_author_id(state: &ApplicationState, post_id: i64) -> Result<i64, ApiError> {
+    sqlx::query_scalar!(
+        "select author_id from posts where id = $1 and deleted_at is null",
+        post_id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("post with such id not found".to_string()))
+}
+
+/// Replies nested deeper than this are shown as replies to the post at this
+/// depth, so a long back-and-forth does not turn into an endless staircase.
+const MAX_REPLY_DEPTH: usize = 16;
+
+/// `posts` are expected in creation order, so a post goes after the one it
+/// replies to; a reply that comes first is shown as a root.
+fn build_posts_tree(posts: Vec<PostDTO>) -> Vec<PostTreeNodeDTO> {
+    let mut depths: HashMap<i64, usize> = HashMap::new();
+    let mut parents: HashMap<i64, Option<i64>> = HashMap::new();
+
+    for post in &posts {
+        // replies to posts that are not listed before them (from other topics,
+        // purged or sorted later) become roots
+        let mut parent = post.reply_to_post_id.filter(|id| depths.contains_key(id));
+        if let Some(parent_id) = parent {
+            if depths
+                .get(&parent_id)
+                .is_some_and(|&depth| depth >= MAX_REPLY_DEPTH)
+            {
+                parent = parents.get(&parent_id).copied().flatten();
+            }
+        }
+
+        let depth = parent
+            .and_then(|parent_id| depths.get(&parent_id))
+            .map_or(0, |depth| depth + 1);
+        depths.insert(post.id, depth);
+        parents.insert(post.id, parent);
+    }
+
+    // going from the newest posts, every node gets all its replies before it
+    // is attached to its own parent
+    let mut replies: HashMap<i64, Vec<PostTreeNodeDTO>> = HashMap::new();
+    let mut roots = Vec::new();
+
+    for post in posts.into_iter().rev() {
+        let mut node_replies = replies.remove(&post.id).unwrap_or_default();
+        node_replies.reverse();
+
+        let parent = parents[&post.id];
+        let node = PostTreeNodeDTO {
+            post,
+            replies: node_replies,
+        };
+
+        match parent {
+            Some(parent_id) => replies.entry(parent_id).or_default().push(node),
+            None => roots.push(node),
+        }
+    }
+
+    roots.reverse();
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn post(id: i64, reply_to_post_id: Option<i64>) -> PostDTO {
+        PostDTO {
+            id,
+            topic_id: 1,
+            text: String::new(),
+            text_html: String::new(),
+            sender: UserDTO {
+                id: 1,
+                login: "user".to_string(),
+            },
+            reply_to_post_id,
+            created_at: Utc::now(),
+            edited_at: None,
+            deleted_at: None,
+            mentions: Vec::new(),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Tree as `(id, replies)` pairs, to compare it with the expected one.
+    fn shape(nodes: &[PostTreeNodeDTO]) -> Vec<(i64, Vec<i64>)> {
+        nodes
+            .iter()
+            .map(|node| {
+                (
+                    node.post.id,
+                    node.replies.iter().map(|reply| reply.post.id).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn replies_are_nested_in_creation_order() {
+        let tree = build_posts_tree(vec![
+            post(1, None),
+            post(2, Some(1)),
+            post(3, None),
+            post(4, Some(2)),
+            post(5, Some(1)),
+        ]);
+
+        assert_eq!(shape(&tree), [(1, vec![2, 5]), (3, vec![])]);
+        assert_eq!(shape(&tree[0].replies), [(2, vec![4]), (5, vec![])]);
+    }
+
+    #[test]
+    fn replies_to_unknown_posts_become_roots() {
+        let tree = build_posts_tree(vec![post(2, Some(3)), post(3, None), post(4, Some(100))]);
+
+        assert_eq!(shape(&tree), [(2, vec![]), (3, vec![]), (4, vec![])]);
+    }
+
+    #[test]
+    fn deep_replies_are_attached_at_max_depth() {
+        let last_id = MAX_REPLY_DEPTH as i64 + 3;
+        let posts = (1..=last_id)
+            .map(|id| post(id, (id > 1).then_some(id - 1)))
+            .collect();
+
+        let tree = build_posts_tree(posts);
+        let mut parent = &tree[0];
+        for _ in 1..MAX_REPLY_DEPTH {
+            parent = &parent.replies[0];
+        }
+
+        assert_eq!(
+            shape(std::slice::from_ref(parent)),
+            [(last_id - 3, vec![last_id - 2, last_id - 1, last_id])]
+        );
+    }
+}
@@ -5,9 +5,11 @@ use axum::{
 };
 
 use crate::{
-    dto::{claims::Claims, reactions::ReactionDTO},
+    dto::{claims::Claims, reactions::ReactionDTO, topic_events::TopicEventDTO},
     errors::ApiError,
     models::Reaction,
+    notifications::notify_about_reaction,
+    policies::ensure_can_post,
     state::ApplicationState,
 };
 
@@ -38,16 +40,68 @@ pub async fn add_reaction(
     State(state): State<ApplicationState>,
     Extension(claims): Extension<Claims>,
 ) -> Result<StatusCode, ApiError> {
-    sqlx::query!(
-        "insert into reactions(post_id, author_id, reaction_id) values ($1, $2, $3)",
+    ensure_can_post(&state, &claims).await?;
+
+    // removed posts and posts of removed topics cannot get reactions; a repeated
+    // reaction, e.g. after a double click, changes nothing
+    let topic_id = sqlx::query_scalar!(
+        r#"
+        insert into reactions(post_id, author_id, reaction_id)
+        select p.id, $2, $3
+        from posts p
+        join topics t on p.topic_id = t.id
+        where p.id = $1 and p.deleted_at is null and t.deleted_at is null
+        on conflict do nothing
+        returning (select topic_id from posts where id = post_id) as "topic_id!"
+        "#,
         post_id,
         claims.user_id,
         reaction_id,
     )
-    .execute(&state.db_pool)
+    .fetch_optional(&state.db_pool)
     .await
     .map_err(|_| ApiError::InternalServerError)?;
 
+    let Some(topic_id) = topic_id else {
+        let already_reacted = sqlx::query_scalar!(
+            r#"
+            select exists (
+                select 1 from reactions
+                where post_id = $1 and author_id = $2 and reaction_id = $3
+            ) as "exists!"
+            "#,
+            post_id,
+            claims.user_id,
+            reaction_id,
+        )
+        .fetch_one(&state.db_pool)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+        return if already_reacted {
+            Ok(StatusCode::OK)
+        } else {
+            Err(ApiError::NotFound(
+                "post with such id not found".to_string(),
+            ))
+        };
+    };
+
+    state.topic_events.publish(
+        topic_id,
+        TopicEventDTO::ReactionAdded {
+            post_id,
+            reaction_id,
+            author_id: claims.user_id,
+        },
+    );
+
+    if let Err(err) =
+        notify_about_reaction(&state.db_pool, post_id, reaction_id, claims.user_id).await
+    {
+        log::warn!("cannot create notification about reaction to post {post_id}: {err}");
+    }
+
     Result::Ok(StatusCode::CREATED)
 }
 
@@ -56,22 +110,33 @@ pub async fn remove_reaction(
     State(state): State<ApplicationState>,
     Extension(claims): Extension<Claims>,
 ) -> Result<StatusCode, ApiError> {
-    let rows_affected = sqlx::query!(
-        "delete from reactions where post_id = $1 and author_id = $2 and reaction_id = $3",
+    let topic_id = sqlx::query_scalar!(
+        r#"
+        delete from reactions where post_id = $1 and author_id = $2 and reaction_id = $3
+        returning (select topic_id from posts where id = post_id) as "topic_id!"
+        "#,
         post_id,
         claims.user_id,
         reaction_id,
     )
-    .execute(&state.db_pool)
+    .fetch_optional(&state.db_pool)
     .await
-    .map_err(|_| ApiError::InternalServerError)?
-    .rows_affected();
+    .map_err(|_| ApiError::InternalServerError)?;
 
-    if rows_affected > 0 {
-        Result::Ok(StatusCode::OK)
-    } else {
-        Err(ApiError::NotFound(
+    match topic_id {
+        Some(topic_id) => {
+            state.topic_events.publish(
+                topic_id,
+                TopicEventDTO::ReactionRemoved {
+                    post_id,
+                    reaction_id,
+                    author_id: claims.user_id,
+                },
+            );
+            Result::Ok(StatusCode::OK)
+        }
+        None => Err(ApiError::NotFound(
             "you did not set this reaction".to_string(),
-        ))
+        )),
     }
 }
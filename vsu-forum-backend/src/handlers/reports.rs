@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Extension, Json,
 };
@@ -8,36 +8,81 @@ use crate::{
     dto::{
         claims::Claims,
         common::ObjectCreatedDTO,
-        reports::{CreateReportDTO, ReportDTO, UpdateReportDTO},
+        pagination::{Cursor, Page, PaginationDTO},
+        reports::{CreateReportDTO, GetReportsDTO, ReportDTO, UpdateReportDTO},
+        users::UserDTO,
     },
     errors::ApiError,
-    extractors::ValidatedJson,
+    extractors::{ValidatedJson, ValidatedQuery},
+    models::{ReportStatus, ReportTargetType, Role},
+    policies::ensure_can_post,
     state::ApplicationState,
 };
 
 pub async fn get_reports(
+    Query(query): Query<GetReportsDTO>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
     State(state): State<ApplicationState>,
-) -> Result<(StatusCode, Json<Vec<ReportDTO>>), ApiError> {
+) -> Result<(StatusCode, Json<Page<ReportDTO>>), ApiError> {
     let reports = sqlx::query!(
         r#"
-        SELECT r.id, r.author_id, u.login as reported_user_name, r.reason
+        SELECT
+            r.id, r.author_id, u.login as reported_user_name, r.reason, r.created_at,
+            r.target_type, r.topic_id, r.post_id, r.content_snapshot,
+            r.status, r.resolution_note, r.updated_at, r.resolved_at,
+            a.id as "assignee_id?", a.login as "assignee_login?"
         FROM reports r
         JOIN users u ON r.reported_user_id = u.id
-        "#
+        LEFT JOIN users a ON r.assignee_id = a.id
+        WHERE
+            ($1::text IS NULL OR r.status = $1)
+            AND ($2::timestamptz IS NULL OR (r.created_at, r.id) < ($2::timestamptz, $3::bigint))
+        ORDER BY r.created_at DESC, r.id DESC
+        LIMIT $4
+        "#,
+        query.status.map(|status| status.as_str()),
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
     )
     .fetch_all(&state.db_pool)
     .await
     .map_err(|_| ApiError::InternalServerError)?
     .into_iter()
-    .map(|r| ReportDTO {
-        id: r.id,
-        author_id: r.author_id,
-        reported_user_name: r.reported_user_name,
-        reason: r.reason,
+    .map(|r| {
+        Ok(ReportDTO {
+            id: r.id,
+            author_id: r.author_id,
+            reported_user_name: r.reported_user_name,
+            reason: r.reason,
+            target_type: r
+                .target_type
+                .parse()
+                .map_err(|_| ApiError::InternalServerError)?,
+            topic_id: r.topic_id,
+            post_id: r.post_id,
+            content_snapshot: r.content_snapshot,
+            status: r
+                .status
+                .parse()
+                .map_err(|_| ApiError::InternalServerError)?,
+            assignee: r
+                .assignee_id
+                .zip(r.assignee_login)
+                .map(|(id, login)| UserDTO { id, login }),
+            resolution_note: r.resolution_note,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+            resolved_at: r.resolved_at,
+        })
     })
-    .collect();
+    .collect::<Result<Vec<_>, ApiError>>()?;
+
+    let page = Page::new(reports, pagination.limit(), |r| {
+        Cursor::new(r.created_at, r.id)
+    });
 
-    Ok((StatusCode::OK, Json(reports)))
+    Ok((StatusCode::OK, Json(page)))
 }
 
 pub async fn get_report(
@@ -46,9 +91,14 @@ pub async fn get_report(
 ) -> Result<(StatusCode, Json<ReportDTO>), ApiError> {
     let report = sqlx::query!(
         r#"
-        SELECT r.id, r.author_id, u.login as reported_user_name, r.reason
+        SELECT
+            r.id, r.author_id, u.login as reported_user_name, r.reason, r.created_at,
+            r.target_type, r.topic_id, r.post_id, r.content_snapshot,
+            r.status, r.resolution_note, r.updated_at, r.resolved_at,
+            a.id as "assignee_id?", a.login as "assignee_login?"
         FROM reports r
         JOIN users u ON r.reported_user_id = u.id
+        LEFT JOIN users a ON r.assignee_id = a.id
         WHERE r.id = $1
         "#,
         id
@@ -64,6 +114,25 @@ pub async fn get_report(
                 author_id: report.author_id,
                 reported_user_name: report.reported_user_name,
                 reason: report.reason,
+                target_type: report
+                    .target_type
+                    .parse()
+                    .map_err(|_| ApiError::InternalServerError)?,
+                topic_id: report.topic_id,
+                post_id: report.post_id,
+                content_snapshot: report.content_snapshot,
+                status: report
+                    .status
+                    .parse()
+                    .map_err(|_| ApiError::InternalServerError)?,
+                assignee: report
+                    .assignee_id
+                    .zip(report.assignee_login)
+                    .map(|(id, login)| UserDTO { id, login }),
+                resolution_note: report.resolution_note,
+                created_at: report.created_at,
+                updated_at: report.updated_at,
+                resolved_at: report.resolved_at,
             };
             Ok((StatusCode::OK, Json(report_dto)))
         }
@@ -76,20 +145,86 @@ pub async fn create_report(
     Extension(claims): Extension<Claims>,
     ValidatedJson(create_report_dto): ValidatedJson<CreateReportDTO>,
 ) -> Result<(StatusCode, Json<ObjectCreatedDTO>), ApiError> {
-    let reported_user_id = sqlx::query_scalar!(
-        "SELECT id FROM users WHERE login = $1",
-        create_report_dto.reported_user_name
-    )
-    .fetch_optional(&state.db_pool)
-    .await
-    .map_err(|_| ApiError::InternalServerError)?
-    .ok_or_else(|| ApiError::NotFound("user not found".to_string()))?;
+    ensure_can_post(&state, &claims).await?;
+
+    let (target_type, reported_user_id, topic_id, post_id, content_snapshot) = match (
+        create_report_dto.reported_user_name,
+        create_report_dto.post_id,
+        create_report_dto.topic_id,
+    ) {
+        (Some(reported_user_name), None, None) => {
+            let reported_user_id =
+                sqlx::query_scalar!("SELECT id FROM users WHERE login = $1", reported_user_name)
+                    .fetch_optional(&state.db_pool)
+                    .await
+                    .map_err(|_| ApiError::InternalServerError)?
+                    .ok_or_else(|| ApiError::NotFound("user not found".to_string()))?;
+
+            (ReportTargetType::User, reported_user_id, None, None, None)
+        }
+        (None, Some(post_id), None) => {
+            let post = sqlx::query!(
+                "
+                SELECT p.author_id, p.topic_id, p.text
+                FROM posts p
+                JOIN topics t ON p.topic_id = t.id
+                WHERE p.id = $1 AND p.deleted_at IS NULL AND t.deleted_at IS NULL
+                ",
+                post_id
+            )
+            .fetch_optional(&state.db_pool)
+            .await
+            .map_err(|_| ApiError::InternalServerError)?
+            .ok_or_else(|| ApiError::NotFound("post not found".to_string()))?;
+
+            (
+                ReportTargetType::Post,
+                post.author_id,
+                Some(post.topic_id),
+                Some(post_id),
+                Some(post.text),
+            )
+        }
+        (None, None, Some(topic_id)) => {
+            let topic = sqlx::query!(
+                "SELECT author_id, name FROM topics WHERE id = $1 AND deleted_at IS NULL",
+                topic_id
+            )
+            .fetch_optional(&state.db_pool)
+            .await
+            .map_err(|_| ApiError::InternalServerError)?
+            .ok_or_else(|| ApiError::NotFound("topic not found".to_string()))?;
+
+            (
+                ReportTargetType::Topic,
+                topic.author_id,
+                Some(topic_id),
+                None,
+                Some(topic.name),
+            )
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "report must target exactly one user, post or topic".to_string(),
+            ))
+        }
+    };
 
     let result = sqlx::query_scalar!(
-        "INSERT INTO reports(author_id, reported_user_id, reason) VALUES ($1, $2, $3) RETURNING id",
+        "
+        INSERT INTO reports(
+            author_id, reported_user_id, reason, target_type, topic_id, post_id, content_snapshot
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id
+        ",
         claims.user_id,
         reported_user_id,
-        create_report_dto.reason
+        create_report_dto.reason,
+        target_type.as_str(),
+        topic_id,
+        post_id,
+        content_snapshot
     )
     .fetch_one(&state.db_pool)
     .await
@@ -120,27 +255,86 @@ pub async fn remove_report(
 pub async fn patch_report(
     Path(report_id): Path<i64>,
     State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
     ValidatedJson(update_report_dto): ValidatedJson<UpdateReportDTO>,
 ) -> Result<StatusCode, ApiError> {
-    let rows_affected = if let Some(reason) = update_report_dto.reason {
-        sqlx::query!(
-            "update reports set reason = $1 where id = $2",
-            reason,
-            report_id
-        )
-        .execute(&state.db_pool)
+    let mut tx = state
+        .db_pool
+        .begin()
         .await
-        .map_err(|_| ApiError::InternalServerError)?
-        .rows_affected()
-    } else {
-        0
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let report = sqlx::query!(
+        "select status, assignee_id, resolution_note from reports where id = $1 for update",
+        report_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("report with such id not found".to_string()))?;
+
+    if let Some(assignee_id) = update_report_dto.assignee_id {
+        let assignee_role: Role =
+            sqlx::query_scalar!("select role from users where id = $1", assignee_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|_| ApiError::InternalServerError)?
+                .ok_or_else(|| ApiError::NotFound("assignee not found".to_string()))?
+                .parse()
+                .map_err(|_| ApiError::InternalServerError)?;
+
+        if assignee_role < Role::Moderator {
+            return Err(ApiError::BadRequest(
+                "reports can be assigned only to moderators".to_string(),
+            ));
+        }
+    }
+
+    let status = match update_report_dto.status {
+        Some(status) => status,
+        None => report
+            .status
+            .parse()
+            .map_err(|_| ApiError::InternalServerError)?,
     };
+    let resolution_note = update_report_dto.resolution_note.or(report.resolution_note);
+    let assignee_id = update_report_dto
+        .assignee_id
+        .or(report.assignee_id)
+        .or((status == ReportStatus::InReview).then_some(claims.user_id));
 
-    if rows_affected > 0 {
-        Result::Ok(StatusCode::OK)
-    } else {
-        Err(ApiError::NotFound(
-            "report with such id not found".to_string(),
-        ))
+    if status.is_closed() && resolution_note.is_none() {
+        return Err(ApiError::BadRequest(
+            "resolution note is required to close a report".to_string(),
+        ));
     }
+
+    sqlx::query!(
+        "
+        update reports
+        set
+            reason = coalesce($1, reason),
+            status = $2,
+            assignee_id = $3,
+            resolution_note = $4,
+            updated_at = now(),
+            resolved_at = case when $5 then coalesce(resolved_at, now()) end
+        where id = $6
+        ",
+        update_report_dto.reason,
+        status.as_str(),
+        assignee_id,
+        resolution_note,
+        status.is_closed(),
+        report_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Result::Ok(StatusCode::OK)
 }
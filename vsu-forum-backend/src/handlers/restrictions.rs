@@ -0,0 +1,213 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::Utc;
+
+use crate::{
+    audit::record_audit_entry,
+    dto::{
+        claims::Claims,
+        common::ObjectCreatedDTO,
+        restrictions::{CreateRestrictionDTO, RestrictionDTO},
+        users::UserDTO,
+    },
+    errors::ApiError,
+    extractors::ValidatedJson,
+    models::{AuditAction, Role, UserRestriction},
+    state::ApplicationState,
+};
+
+pub async fn get_user_restrictions(
+    Path(user_id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<Vec<RestrictionDTO>>), ApiError> {
+    let restrictions = sqlx::query!(
+        r#"
+        SELECT
+            r.id, r.user_id, r.kind, r.reason, r.created_at, r.expires_at, r.revoked_at,
+            u.id as "issuer_id?", u.login as "issuer_login?"
+        FROM user_restrictions r
+        LEFT JOIN users u ON r.issued_by = u.id
+        WHERE r.user_id = $1
+        ORDER BY r.created_at DESC, r.id DESC
+        "#,
+        user_id
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|r| {
+        Ok(RestrictionDTO {
+            id: r.id,
+            user_id: r.user_id,
+            kind: r.kind.parse().map_err(|_| ApiError::InternalServerError)?,
+            reason: r.reason,
+            issued_by: r
+                .issuer_id
+                .zip(r.issuer_login)
+                .map(|(id, login)| UserDTO { id, login }),
+            created_at: r.created_at,
+            expires_at: r.expires_at,
+            revoked_at: r.revoked_at,
+        })
+    })
+    .collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok((StatusCode::OK, Json(restrictions)))
+}
+
+pub async fn create_user_restriction(
+    Path(user_id): Path<i64>,
+    State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
+    ValidatedJson(create_restriction_dto): ValidatedJson<CreateRestrictionDTO>,
+) -> Result<(StatusCode, Json<ObjectCreatedDTO>), ApiError> {
+    ensure_lower_role(&state, &claims, user_id, "restrict").await?;
+
+    if create_restriction_dto
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(ApiError::BadRequest(
+            "expiry time must be in the future".to_string(),
+        ));
+    }
+
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let restriction = sqlx::query_as!(
+        UserRestriction,
+        "
+        insert into user_restrictions(user_id, kind, reason, issued_by, expires_at)
+        values ($1, $2, $3, $4, $5)
+        returning id, user_id, kind, reason, issued_by, created_at, expires_at, revoked_at
+        ",
+        user_id,
+        create_restriction_dto.kind.as_str(),
+        create_restriction_dto.reason,
+        claims.user_id,
+        create_restriction_dto.expires_at
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    record_audit_entry(
+        &mut tx,
+        claims.user_id,
+        AuditAction::RestrictUser,
+        user_id,
+        None,
+        Some(&restriction),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ObjectCreatedDTO { id: restriction.id }),
+    ))
+}
+
+pub async fn revoke_user_restriction(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<StatusCode, ApiError> {
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let restriction = sqlx::query_as!(
+        UserRestriction,
+        "
+        select id, user_id, kind, reason, issued_by, created_at, expires_at, revoked_at
+        from user_restrictions
+        where id = $1 and revoked_at is null
+        for update
+        ",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("active restriction with such id not found".to_string()))?;
+
+    // a restriction can be lifted only by its issuer or by someone who could
+    // have issued it over the issuer's head
+    ensure_lower_role(&state, &claims, restriction.user_id, "unrestrict").await?;
+    if let Some(issuer_id) = restriction.issued_by.filter(|&id| id != claims.user_id) {
+        if fetch_user_role(&state, issuer_id).await? >= claims.role {
+            return Err(ApiError::Forbidden(
+                "you can revoke only restrictions issued by users with a lower role".to_string(),
+            ));
+        }
+    }
+
+    let revoked_restriction = sqlx::query_as!(
+        UserRestriction,
+        "
+        update user_restrictions set revoked_at = now()
+        where id = $1
+        returning id, user_id, kind, reason, issued_by, created_at, expires_at, revoked_at
+        ",
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    record_audit_entry(
+        &mut tx,
+        claims.user_id,
+        AuditAction::RevokeUserRestriction,
+        restriction.user_id,
+        Some(&restriction),
+        Some(&revoked_restriction),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(StatusCode::OK)
+}
+
+/// `action` names what is done to the user in the error message.
+async fn ensure_lower_role(
+    state: &ApplicationState,
+    claims: &Claims,
+    user_id: i64,
+    action: &str,
+) -> Result<(), ApiError> {
+    if fetch_user_role(state, user_id).await? >= claims.role {
+        return Err(ApiError::Forbidden(format!(
+            "you can {action} only users with a lower role"
+        )));
+    }
+
+    Ok(())
+}
+
+async fn fetch_user_role(state: &ApplicationState, user_id: i64) -> Result<Role, ApiError> {
+    sqlx::query_scalar!("select role from users where id = $1", user_id)
+        .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+        .ok_or_else(|| ApiError::NotFound("user not found".to_string()))?
+        .parse()
+        .map_err(|_| ApiError::InternalServerError)
+}
@@ -1,27 +1,61 @@
-use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    Json,
-};
+use axum::{extract::State, http::StatusCode, Json};
 
 use crate::{
+    attachments::attach_post_attachments,
     dto::{
-        search::SearchQueryParamsDTO, topic_categories::TopicCategoryDTO, topics::TopicDTO,
+        pagination::{OffsetCursor, PaginationDTO},
+        posts::PostDTO,
+        search::{
+            PostSearchHitDTO, SearchPage, SearchQueryParamsDTO, SearchResultType, SearchResultsDTO,
+            TopicSearchHitDTO,
+        },
+        topic_categories::TopicCategoryDTO,
+        topics::TopicDTO,
         users::UserDTO,
     },
     errors::ApiError,
+    extractors::ValidatedQuery,
+    mentions::attach_mentions,
     state::ApplicationState,
+    tools::render_markdown,
 };
 
+/// Control characters mark the found words in headlines instead of tags, so
+/// the text around them can be escaped before the tags are put in. They are
+/// removed from the searched text first.
+const HEADLINE_START: char = '\u{2}';
+const HEADLINE_STOP: char = '\u{3}';
+const HEADLINE_OPTIONS: &str = "StartSel=\u{2}, StopSel=\u{3}, MaxWords=30, MinWords=10";
+
 pub async fn search(
     State(state): State<ApplicationState>,
-    Query(query): Query<SearchQueryParamsDTO>,
-) -> Result<(StatusCode, Json<Vec<TopicDTO>>), ApiError> {
-    let search_pattern = format!("%{}%", query.query.to_lowercase());
+    ValidatedQuery(query): ValidatedQuery<SearchQueryParamsDTO>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO<OffsetCursor>>,
+) -> Result<(StatusCode, Json<SearchResultsDTO>), ApiError> {
+    let results = match query.result_type {
+        SearchResultType::Topics => {
+            SearchResultsDTO::Topics(search_topics(&state, &query, &pagination).await?)
+        }
+        SearchResultType::Posts => {
+            SearchResultsDTO::Posts(search_posts(&state, &query, &pagination).await?)
+        }
+        SearchResultType::Users => {
+            SearchResultsDTO::Users(search_users(&state, &query, &pagination).await?)
+        }
+    };
 
-    let topics: Vec<TopicDTO> = sqlx::query!(
-        "
-        SELECT DISTINCT
+    Ok((StatusCode::OK, Json(results)))
+}
+
+async fn search_topics(
+    state: &ApplicationState,
+    query: &SearchQueryParamsDTO,
+    pagination: &PaginationDTO<OffsetCursor>,
+) -> Result<SearchPage<TopicSearchHitDTO>, ApiError> {
+    let topics = sqlx::query!(
+        r#"
+        WITH q AS (SELECT websearch_to_tsquery('forum', $1) AS query)
+        SELECT
             t.id AS topic_id,
             t.category_id AS category_id,
             t.name AS topic_name,
@@ -29,52 +63,239 @@ pub async fn search(
             u.id AS creator_id,
             u.login AS creator_login,
             tc.name AS category_name,
-            COUNT(p.id) AS posts_count
+            COUNT(p.id) AS posts_count,
+            ts_rank(t.search_vector, q.query) AS "rank!",
+            ts_headline('forum', translate(t.name, E'\x02\x03', ''), q.query, $2) AS "headline!"
         FROM
             topics t
+        CROSS JOIN
+            q
         JOIN
             users u ON t.author_id = u.id
         JOIN
             topics_categories tc ON t.category_id = tc.id
         LEFT JOIN
-            posts p ON t.id = p.topic_id
+            posts p ON t.id = p.topic_id AND p.deleted_at IS NULL
         WHERE
-            LOWER(t.name) LIKE $1
-            OR LOWER(tc.name) LIKE $1
-            OR LOWER(u.login) LIKE $1
-            OR EXISTS (
-                SELECT 1
-                FROM posts p2
-                WHERE p2.topic_id = t.id
-                AND LOWER(p2.text) LIKE $1
-            )
+            t.search_vector @@ q.query
+            AND t.deleted_at IS NULL
+            AND ($3::bigint IS NULL OR t.category_id = $3)
+            AND ($4::text IS NULL OR u.login = $4)
+            AND ($5::timestamptz IS NULL OR t.created_at >= $5)
+            AND ($6::timestamptz IS NULL OR t.created_at < $6)
         GROUP BY
-            t.id, t.author_id, t.category_id, t.name, u.id, u.login, tc.name
+            t.id, u.id, tc.id, q.query
         ORDER BY
-            t.id DESC
-        LIMIT 50
-        ",
-        search_pattern
+            "rank!" DESC, t.id DESC
+        LIMIT $7
+        OFFSET $8
+        "#,
+        query.query,
+        HEADLINE_OPTIONS,
+        query.category_id,
+        query.author,
+        query.created_from,
+        query.created_to,
+        pagination.limit() + 1,
+        pagination.offset()
     )
     .fetch_all(&state.db_pool)
     .await
     .map_err(|_| ApiError::InternalServerError)?
-    .iter()
-    .map(|record| TopicDTO {
-        id: record.topic_id,
-        created_at: record.created_at,
-        name: record.topic_name.clone(),
-        category: TopicCategoryDTO {
-            id: record.category_id,
-            name: record.category_name.clone(),
+    .into_iter()
+    .map(|record| TopicSearchHitDTO {
+        topic: TopicDTO {
+            id: record.topic_id,
+            created_at: record.created_at,
+            name: record.topic_name,
+            category: TopicCategoryDTO {
+                id: record.category_id,
+                name: record.category_name,
+            },
+            creator: UserDTO {
+                id: record.creator_id,
+                login: record.creator_login,
+            },
+            posts_count: record.posts_count.unwrap_or(0),
         },
-        creator: UserDTO {
-            id: record.creator_id,
-            login: record.creator_login.clone(),
+        headline: headline_html(&record.headline),
+        rank: record.rank,
+    })
+    .collect();
+
+    Ok(SearchPage::with_offset(
+        topics,
+        pagination.limit(),
+        pagination.offset(),
+    ))
+}
+
+async fn search_posts(
+    state: &ApplicationState,
+    query: &SearchQueryParamsDTO,
+    pagination: &PaginationDTO<OffsetCursor>,
+) -> Result<SearchPage<PostSearchHitDTO>, ApiError> {
+    let posts: Vec<PostSearchHitDTO> = sqlx::query!(
+        r#"
+        WITH q AS (SELECT websearch_to_tsquery('forum', $1) AS query)
+        SELECT
+            p.id AS post_id,
+            p.topic_id AS topic_id,
+            p.created_at as created_at,
+            p.edited_at,
+            p.text AS post_text,
+            p.reply_to_post_id,
+            u.id AS sender_id,
+            u.login AS sender_login,
+            t.name AS topic_name,
+            ts_rank(p.search_vector, q.query) AS "rank!",
+            ts_headline('forum', translate(p.text, E'\x02\x03', ''), q.query, $2) AS "headline!"
+        FROM
+            posts p
+        CROSS JOIN
+            q
+        JOIN
+            users u ON p.author_id = u.id
+        JOIN
+            topics t ON p.topic_id = t.id
+        WHERE
+            p.search_vector @@ q.query
+            AND p.deleted_at IS NULL
+            AND t.deleted_at IS NULL
+            AND ($3::bigint IS NULL OR t.category_id = $3)
+            AND ($4::text IS NULL OR u.login = $4)
+            AND ($5::timestamptz IS NULL OR p.created_at >= $5)
+            AND ($6::timestamptz IS NULL OR p.created_at < $6)
+        ORDER BY
+            "rank!" DESC, p.id DESC
+        LIMIT $7
+        OFFSET $8
+        "#,
+        query.query,
+        HEADLINE_OPTIONS,
+        query.category_id,
+        query.author,
+        query.created_from,
+        query.created_to,
+        pagination.limit() + 1,
+        pagination.offset()
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|record| PostSearchHitDTO {
+        post: PostDTO {
+            id: record.post_id,
+            topic_id: record.topic_id,
+            text_html: render_markdown(&record.post_text),
+            text: record.post_text,
+            sender: UserDTO {
+                id: record.sender_id,
+                login: record.sender_login,
+            },
+            reply_to_post_id: record.reply_to_post_id,
+            created_at: record.created_at,
+            edited_at: record.edited_at,
+            deleted_at: None,
+            mentions: Vec::new(),
+            attachments: Vec::new(),
         },
-        posts_count: record.posts_count.unwrap_or(0),
+        topic_name: record.topic_name,
+        headline: headline_html(&record.headline),
+        rank: record.rank,
     })
     .collect();
 
-    Ok((StatusCode::OK, Json(topics)))
+    let mut page = SearchPage::with_offset(posts, pagination.limit(), pagination.offset());
+    attach_mentions(
+        &state.db_pool,
+        page.items.iter_mut().map(|hit| &mut hit.post),
+    )
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+    attach_post_attachments(
+        &state.db_pool,
+        page.items.iter_mut().map(|hit| &mut hit.post),
+    )
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(page)
+}
+
+async fn search_users(
+    state: &ApplicationState,
+    query: &SearchQueryParamsDTO,
+    pagination: &PaginationDTO<OffsetCursor>,
+) -> Result<SearchPage<UserDTO>, ApiError> {
+    let pattern = format!(
+        "%{}%",
+        query
+            .query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+
+    let users = sqlx::query!(
+        "
+        SELECT id, login
+        FROM users
+        WHERE login ILIKE $1 AND deleted_at IS NULL
+        ORDER BY LOWER(login) = LOWER($2) DESC, LENGTH(login), id
+        LIMIT $3
+        OFFSET $4
+        ",
+        pattern,
+        query.query,
+        pagination.limit() + 1,
+        pagination.offset()
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|record| UserDTO {
+        id: record.id,
+        login: record.login,
+    })
+    .collect();
+
+    Ok(SearchPage::with_offset(
+        users,
+        pagination.limit(),
+        pagination.offset(),
+    ))
+}
+
+/// Escapes the headline and wraps the found words in `<mark>` tags.
+fn headline_html(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            HEADLINE_START => html.push_str("<mark>"),
+            HEADLINE_STOP => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headline_text_is_escaped_around_marks() {
+        assert_eq!(
+            headline_html("<img src=x onerror=\"alert('\u{2}cat\u{3}')\"> & co"),
+            "&lt;img src=x onerror=&quot;alert(&#39;<mark>cat</mark>&#39;)&quot;&gt; &amp; co"
+        );
+    }
 }
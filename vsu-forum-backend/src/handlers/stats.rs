@@ -8,9 +8,9 @@ pub async fn get_stats(
     let stats = sqlx::query!(
         r#"
             SELECT
-                (SELECT COUNT(*) FROM posts) as posts_count,
+                (SELECT COUNT(*) FROM posts WHERE deleted_at IS NULL) as posts_count,
                 (SELECT COUNT(*) FROM users) as users_count,
-                (SELECT COUNT(*) FROM topics) as topics_count
+                (SELECT COUNT(*) FROM topics WHERE deleted_at IS NULL) as topics_count
             "#
     )
     .fetch_one(&state.db_pool)
@@ -0,0 +1,52 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use tokio_stream::{
+    wrappers::{errors::BroadcastStreamRecvError, BroadcastStream},
+    Stream, StreamExt,
+};
+
+use crate::{errors::ApiError, state::ApplicationState};
+
+pub async fn get_topic_events(
+    Path(topic_id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    // subscribe first, so nothing happening during the check is missed
+    let receiver = state.topic_events.subscribe();
+
+    let topic_exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM topics WHERE id = $1 AND deleted_at IS NULL) AS "exists!""#,
+        topic_id
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if !topic_exists {
+        return Err(ApiError::NotFound("topic not found".to_string()));
+    }
+
+    let stream = BroadcastStream::new(receiver).filter_map(move |message| match message {
+        Ok(message) if message.topic_id == topic_id => {
+            let event = Event::default().event(message.event.name());
+
+            match event.json_data(&*message.event) {
+                Ok(event) => Some(Ok(event)),
+                Err(err) => {
+                    log::warn!("cannot serialize topic event: {err}");
+                    None
+                }
+            }
+        }
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(skipped)) => Some(Ok(Event::default()
+            .event("lagged")
+            .data(skipped.to_string()))),
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -5,21 +5,27 @@ use axum::{
 };
 
 use crate::{
+    audit::record_audit_entry,
     dto::{
         claims::Claims,
         common::ObjectCreatedDTO,
+        pagination::{Cursor, Page, PaginationDTO},
         topic_categories::TopicCategoryDTO,
         topics::{CreateTopicDTO, TopicDTO, UpdateTopicDTO},
         users::UserDTO,
     },
     errors::ApiError,
-    extractors::ValidatedJson,
+    extractors::{ValidatedJson, ValidatedQuery},
+    handlers::users::ensure_user_exists,
+    models::{AuditAction, Topic},
+    policies::{ensure_can_modify, ensure_can_post},
     state::ApplicationState,
 };
 
 pub async fn get_topics(
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
     State(state): State<ApplicationState>,
-) -> Result<(StatusCode, Json<Vec<TopicDTO>>), ApiError> {
+) -> Result<(StatusCode, Json<Page<TopicDTO>>), ApiError> {
     let topics = sqlx::query!(
         "
         SELECT
@@ -38,10 +44,19 @@ pub async fn get_topics(
         JOIN
             topics_categories tc ON t.category_id = tc.id
         LEFT JOIN
-            posts p ON t.id = p.topic_id
+            posts p ON t.id = p.topic_id AND p.deleted_at IS NULL
+        WHERE
+            t.deleted_at IS NULL
+            AND ($1::timestamptz IS NULL OR (t.created_at, t.id) < ($1::timestamptz, $2::bigint))
         GROUP BY
-            t.id, t.author_id, t.category_id, t.name, u.id, u.login, tc.name;
-        "
+            t.id, t.author_id, t.category_id, t.name, u.id, u.login, tc.name
+        ORDER BY
+            t.created_at DESC, t.id DESC
+        LIMIT $3;
+        ",
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
     )
     .fetch_all(&state.db_pool)
     .await
@@ -63,7 +78,80 @@ pub async fn get_topics(
     })
     .collect();
 
-    Ok((StatusCode::OK, Json(topics)))
+    let page = Page::new(topics, pagination.limit(), |t| {
+        Cursor::new(t.created_at, t.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
+}
+
+/// Topics created by the user, newest first.
+pub async fn get_user_topics(
+    Path(user_id): Path<i64>,
+    ValidatedQuery(pagination): ValidatedQuery<PaginationDTO>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<Page<TopicDTO>>), ApiError> {
+    ensure_user_exists(&state, user_id).await?;
+
+    let topics = sqlx::query!(
+        "
+        SELECT
+            t.id AS topic_id,
+            t.category_id AS category_id,
+            t.name AS topic_name,
+            t.created_at as created_at,
+            u.id AS creator_id,
+            u.login AS creator_login,
+            tc.name AS category_name,
+            COUNT(p.id) AS posts_count
+        FROM
+            topics t
+        JOIN
+            users u ON t.author_id = u.id
+        JOIN
+            topics_categories tc ON t.category_id = tc.id
+        LEFT JOIN
+            posts p ON t.id = p.topic_id AND p.deleted_at IS NULL
+        WHERE
+            t.deleted_at IS NULL
+            AND t.author_id = $1
+            AND ($2::timestamptz IS NULL OR (t.created_at, t.id) < ($2::timestamptz, $3::bigint))
+        GROUP BY
+            t.id, t.author_id, t.category_id, t.name, u.id, u.login, tc.name
+        ORDER BY
+            t.created_at DESC, t.id DESC
+        LIMIT $4;
+        ",
+        user_id,
+        pagination.cursor_created_at(),
+        pagination.cursor_id(),
+        pagination.limit() + 1
+    )
+    .fetch_all(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .into_iter()
+    .map(|record| TopicDTO {
+        id: record.topic_id,
+        created_at: record.created_at,
+        name: record.topic_name,
+        category: TopicCategoryDTO {
+            id: record.category_id,
+            name: record.category_name,
+        },
+        creator: UserDTO {
+            id: record.creator_id,
+            login: record.creator_login,
+        },
+        posts_count: record.posts_count.unwrap_or(0),
+    })
+    .collect();
+
+    let page = Page::new(topics, pagination.limit(), |t| {
+        Cursor::new(t.created_at, t.id)
+    });
+
+    Ok((StatusCode::OK, Json(page)))
 }
 
 pub async fn get_topic(
@@ -88,9 +176,10 @@ pub async fn get_topic(
         JOIN
             topics_categories tc ON t.category_id = tc.id
         LEFT JOIN
-            posts p ON t.id = p.topic_id
+            posts p ON t.id = p.topic_id AND p.deleted_at IS NULL
         WHERE
             t.id = $1
+            AND t.deleted_at IS NULL
         GROUP BY
             t.id, t.author_id, t.category_id, t.name, u.id, u.login, tc.name;
         ",
@@ -127,6 +216,8 @@ pub async fn create_topic(
     Extension(claims): Extension<Claims>,
     ValidatedJson(create_topic_dto): ValidatedJson<CreateTopicDTO>,
 ) -> Result<(StatusCode, Json<ObjectCreatedDTO>), ApiError> {
+    ensure_can_post(&state, &claims).await?;
+
     let result = sqlx::query_scalar!(
         "insert into topics(author_id, category_id, name) values ($1, $2, $3) returning id",
         claims.user_id,
@@ -143,18 +234,80 @@ pub async fn create_topic(
 pub async fn remove_topic(
     Path(id): Path<i64>,
     State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
 ) -> Result<StatusCode, ApiError> {
-    let rows_affected = sqlx::query!("delete from topics where id = $1", id)
-        .execute(&state.db_pool)
+    let mut tx = state
+        .db_pool
+        .begin()
         .await
-        .map_err(|_| ApiError::InternalServerError)?
-        .rows_affected();
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let topic = sqlx::query_as!(
+        Topic,
+        "
+        select id, author_id, category_id, name, created_at, deleted_at, deleted_by
+        from topics
+        where id = $1 and deleted_at is null
+        for update
+        ",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("topic with such id not found".to_string()))?;
+
+    ensure_can_modify(&claims, topic.author_id)?;
+
+    let removed_topic = sqlx::query_as!(
+        Topic,
+        "
+        update topics set deleted_at = now(), deleted_by = $2
+        where id = $1
+        returning id, author_id, category_id, name, created_at, deleted_at, deleted_by
+        ",
+        id,
+        claims.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    record_audit_entry(
+        &mut tx,
+        claims.user_id,
+        AuditAction::RemoveTopic,
+        id,
+        Some(&topic),
+        Some(&removed_topic),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    Result::Ok(StatusCode::OK)
+}
+
+pub async fn restore_topic(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<StatusCode, ApiError> {
+    let rows_affected = sqlx::query!(
+        "update topics set deleted_at = null, deleted_by = null where id = $1 and deleted_at is not null",
+        id
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .rows_affected();
 
     if rows_affected > 0 {
         Result::Ok(StatusCode::OK)
     } else {
         Err(ApiError::NotFound(
-            "topic with such id not found".to_string(),
+            "removed topic with such id not found".to_string(),
         ))
     }
 }
@@ -162,8 +315,13 @@ pub async fn remove_topic(
 pub async fn patch_topic(
     Path(topic_id): Path<i64>,
     State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
     ValidatedJson(update_topic_dto): ValidatedJson<UpdateTopicDTO>,
 ) -> Result<StatusCode, ApiError> {
+    ensure_can_post(&state, &claims).await?;
+    let author_id = get_topic_author_id(&state, topic_id).await?;
+    ensure_can_modify(&claims, author_id)?;
+
     let mut rows_affected = 0;
 
     if let Some(name) = update_topic_dto.name {
@@ -193,3 +351,14 @@ pub async fn patch_topic(
         ))
     }
 }
+
+async fn get_topic_author_id(state: &ApplicationState, topic_id: i64) -> Result<i64, ApiError> {
+    sqlx::query_scalar!(
+        "select author_id from topics where id = $1 and deleted_at is null",
+        topic_id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("topic with such id not found".to_string()))
+}
@@ -1,18 +1,21 @@
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
 
 use crate::{
+    audit::record_audit_entry,
     dto::{
+        claims::Claims,
         common::ObjectCreatedDTO,
         topic_categories::{CreateTopicCategoryDTO, TopicCategoryDTO},
     },
     errors::ApiError,
     extractors::ValidatedJson,
-    models::TopicCategory,
+    models::{AuditAction, TopicCategory},
     state::ApplicationState,
+    uploads::delete_stored_files,
 };
 
 pub async fn get_topic_categories(
@@ -72,21 +75,86 @@ pub async fn create_topic_category(
     Result::Ok((StatusCode::CREATED, Json(ObjectCreatedDTO { id: result })))
 }
 
+/// Only categories without visible topics can be removed. Removed topics
+/// waiting for the purge go away with the category, together with the files
+/// of their attachments.
 pub async fn remove_topic_category(
     Path(id): Path<i64>,
     State(state): State<ApplicationState>,
+    Extension(claims): Extension<Claims>,
 ) -> Result<StatusCode, ApiError> {
-    let rows_affected = sqlx::query!("delete from topics_categories where id = $1", id)
-        .execute(&state.db_pool)
+    let mut tx = state
+        .db_pool
+        .begin()
         .await
-        .map_err(|_| ApiError::InternalServerError)?
-        .rows_affected();
-
-    if rows_affected > 0 {
-        Result::Ok(StatusCode::OK)
-    } else {
-        Err(ApiError::NotFound(
-            "topic category with such id not found".to_string(),
-        ))
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    // the lock keeps new topics out of the category until it is deleted
+    let topic_category = sqlx::query_as!(
+        TopicCategory,
+        "select id, name from topics_categories where id = $1 for update",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("topic category with such id not found".to_string()))?;
+
+    let has_topics = sqlx::query_scalar!(
+        r#"
+        select exists (
+            select 1 from topics where category_id = $1 and deleted_at is null
+        ) as "has_topics!"
+        "#,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if has_topics {
+        return Err(ApiError::BadRequest(
+            "topic category is not empty, move or remove its topics first".to_string(),
+        ));
     }
+
+    let attachments = sqlx::query!(
+        "
+        delete from attachments a
+        using posts p, topics t
+        where a.post_id = p.id and p.topic_id = t.id and t.category_id = $1
+        returning a.storage_key, a.thumbnail_key
+        ",
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!("delete from topics_categories where id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    record_audit_entry(
+        &mut tx,
+        claims.user_id,
+        AuditAction::RemoveTopicCategory,
+        id,
+        Some(&topic_category),
+        None,
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let keys = attachments
+        .into_iter()
+        .flat_map(|attachment| [Some(attachment.storage_key), attachment.thumbnail_key])
+        .flatten();
+    delete_stored_files(state.storage.as_ref(), keys).await;
+
+    Result::Ok(StatusCode::OK)
 }
@@ -1,6 +1,9 @@
+use std::net::SocketAddr;
+
 use axum::{
-    extract::{Path, State},
+    extract::{ConnectInfo, Multipart, Path, State},
     http::StatusCode,
+    response::Response,
     Extension, Json,
 };
 use chrono::{Duration, Utc};
@@ -9,15 +12,28 @@ use jsonwebtoken::{encode, EncodingKey, Header};
 use crate::{
     dto::{
         claims::Claims,
-        users::{AuthorizedUserDTO, LoginDTO, RegisterDTO, UserDTO},
+        users::{
+            AuthorizedUserDTO, CurrentUserDTO, LoginDTO, RefreshTokenDTO, RegisterDTO,
+            UpdateProfileDTO, UserProfileDTO,
+        },
     },
     errors::ApiError,
     extractors::ValidatedJson,
-    models::User,
+    handlers::account::{ensure_email_usable, send_verification_mail, DELETED_LOGIN_PREFIX},
+    login_protection::{ensure_login_allowed, record_failed_login, record_successful_login},
+    models::{Role, User},
+    policies::ensure_can_post,
     state::ApplicationState,
-    tools::hash_text,
+    tools::{
+        dummy_password_hash, generate_secret_token, generate_storage_key, hash_password,
+        hash_secret_token, verify_password, PasswordVerification,
+    },
+    uploads::{delete_stored_files, read_upload, stored_file_response},
 };
 
+/// Name of the multipart field with the avatar image.
+const AVATAR_FIELD: &str = "file";
+
 pub async fn register_user(
     State(state): State<ApplicationState>,
     ValidatedJson(register_dto): ValidatedJson<RegisterDTO>,
@@ -31,116 +47,550 @@ pub async fn register_user(
     .await
     .map_err(|_| ApiError::InternalServerError)?;
 
+    if register_dto.login.starts_with(DELETED_LOGIN_PREFIX) {
+        return Err(ApiError::BadRequest("this login is reserved".to_string()));
+    }
+
     match user {
         Some(_) => Err(ApiError::BadRequest(
             "user with this login already registered".to_string(),
         )),
         None => {
+            let email = register_dto.email.map(|email| email.trim().to_string());
+            if let Some(email) = &email {
+                ensure_email_usable(&state, email).await?;
+            }
+
+            let password_hash =
+                hash_password(register_dto.password, state.config.password_hashing.clone())
+                    .await
+                    .map_err(|_| ApiError::InternalServerError)?;
+
             let result = sqlx::query_scalar!(
-                "insert into users(login, password_hash) values ($1, $2) returning id",
+                "insert into users(login, password_hash, email) values ($1, $2, $3) returning id",
                 register_dto.login,
-                hash_text(register_dto.password)
+                password_hash,
+                email
             )
             .fetch_one(&state.db_pool)
             .await
             .map_err(|_| ApiError::InternalServerError)?;
 
-            let claims = Claims {
-                exp: (Utc::now() + Duration::days(31)).timestamp() as usize,
-                sub: register_dto.login,
-                user_id: result,
-            };
-            let token = encode(
-                &Header::default(),
-                &claims,
-                &EncodingKey::from_secret(state.config.jwt.secret_key.as_ref()),
-            )
-            .map_err(|_| ApiError::InternalServerError)?;
+            // the account works without it, and the mail can be requested again
+            if let Some(email) = &email {
+                if let Err(err) =
+                    send_verification_mail(&state, result, &register_dto.login, email).await
+                {
+                    log::warn!("cannot send verification mail to user {result}: {err}");
+                }
+            }
+
+            let authorized_user =
+                start_session(&state, result, register_dto.login, Role::User).await?;
 
-            Ok((
-                StatusCode::CREATED,
-                Json(AuthorizedUserDTO { token, id: result }),
-            ))
+            Ok((StatusCode::CREATED, Json(authorized_user)))
         }
     }
 }
 
 pub async fn login_user(
     State(state): State<ApplicationState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     ValidatedJson(login_dto): ValidatedJson<LoginDTO>,
 ) -> Result<(StatusCode, Json<AuthorizedUserDTO>), ApiError> {
+    let ip = addr.ip().to_string();
+    ensure_login_allowed(&state.db_pool, &login_dto.login, &ip).await?;
+
     let user = sqlx::query_as!(
         User,
-        "select * from users where login = $1 and password_hash = $2",
-        login_dto.login,
-        hash_text(login_dto.password)
+        "select * from users where login = $1 and deleted_at is null",
+        login_dto.login
     )
     .fetch_optional(&state.db_pool)
     .await
     .map_err(|_| ApiError::InternalServerError)?;
 
-    match user {
+    let password_hash = match &user {
+        Some(user) => user.password_hash.clone(),
+        None => dummy_password_hash(&state.config.password_hashing)
+            .await
+            .map_err(|_| ApiError::InternalServerError)?
+            .to_string(),
+    };
+    let verification = verify_password(
+        login_dto.password.clone(),
+        password_hash,
+        state.config.password_hashing.clone(),
+    )
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if let (Some(user), PasswordVerification::ValidNeedsRehash) = (&user, &verification) {
+        rehash_password(&state, user.id, login_dto.password).await;
+    }
+
+    match user.filter(|_| !matches!(verification, PasswordVerification::Invalid)) {
         Some(user) => {
-            let claims = Claims {
-                exp: (Utc::now() + Duration::days(31)).timestamp() as usize,
-                sub: user.login,
-                user_id: user.id,
-            };
-            let token = encode(
-                &Header::default(),
-                &claims,
-                &EncodingKey::from_secret(state.config.jwt.secret_key.as_ref()),
+            let role = user
+                .role
+                .parse()
+                .map_err(|_| ApiError::InternalServerError)?;
+            record_successful_login(&state.db_pool, user.id, &user.login, &ip).await?;
+            let authorized_user = start_session(&state, user.id, user.login, role).await?;
+
+            Ok((StatusCode::OK, Json(authorized_user)))
+        }
+        None => {
+            let attempts = record_failed_login(
+                &state.db_pool,
+                &state.config.login_protection,
+                &login_dto.login,
+                &ip,
             )
-            .map_err(|_| ApiError::InternalServerError)?;
+            .await?;
 
-            Ok((
-                StatusCode::OK,
-                Json(AuthorizedUserDTO { token, id: user.id }),
-            ))
+            Err(ApiError::LoginFailed(attempts))
         }
-        None => Err(ApiError::OtherError(
+    }
+}
+
+pub async fn refresh_tokens(
+    State(state): State<ApplicationState>,
+    ValidatedJson(refresh_dto): ValidatedJson<RefreshTokenDTO>,
+) -> Result<(StatusCode, Json<AuthorizedUserDTO>), ApiError> {
+    let refresh_token = generate_secret_token();
+
+    // the old refresh token stops working as soon as the new one is issued
+    let session = sqlx::query!(
+        r#"
+        UPDATE sessions s
+        SET refresh_token_hash = $1, expires_at = $2
+        FROM users u
+        WHERE
+            s.user_id = u.id
+            AND s.refresh_token_hash = $3
+            AND s.revoked_at IS NULL
+            AND s.expires_at > NOW()
+        RETURNING s.id AS session_id, u.id AS user_id, u.login, u.role
+        "#,
+        hash_secret_token(&refresh_token),
+        Utc::now() + Duration::days(state.config.jwt.refresh_token_ttl_days),
+        hash_secret_token(&refresh_dto.refresh_token)
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| {
+        ApiError::OtherError(
             StatusCode::UNAUTHORIZED,
-            "login or password is incorrect".to_string(),
-        )),
+            "refresh token is incorrect or expired".to_string(),
+        )
+    })?;
+
+    let role = session
+        .role
+        .parse()
+        .map_err(|_| ApiError::InternalServerError)?;
+    let token = encode_access_token(
+        &state,
+        session.user_id,
+        session.login,
+        role,
+        session.session_id,
+    )?;
+
+    Ok((
+        StatusCode::OK,
+        Json(AuthorizedUserDTO {
+            id: session.user_id,
+            token,
+            refresh_token,
+        }),
+    ))
+}
+
+pub async fn logout_user(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+) -> Result<StatusCode, ApiError> {
+    sqlx::query!(
+        "update sessions set revoked_at = now() where id = $1 and revoked_at is null",
+        claims.session_id
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn logout_user_everywhere(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+) -> Result<StatusCode, ApiError> {
+    sqlx::query!(
+        "update sessions set revoked_at = now() where user_id = $1 and revoked_at is null",
+        claims.user_id
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn start_session(
+    state: &ApplicationState,
+    user_id: i64,
+    login: String,
+    role: Role,
+) -> Result<AuthorizedUserDTO, ApiError> {
+    let refresh_token = generate_secret_token();
+
+    let session_id = sqlx::query_scalar!(
+        "insert into sessions(user_id, refresh_token_hash, expires_at) values ($1, $2, $3) returning id",
+        user_id,
+        hash_secret_token(&refresh_token),
+        Utc::now() + Duration::days(state.config.jwt.refresh_token_ttl_days)
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    let token = encode_access_token(state, user_id, login, role, session_id)?;
+
+    Ok(AuthorizedUserDTO {
+        id: user_id,
+        token,
+        refresh_token,
+    })
+}
+
+fn encode_access_token(
+    state: &ApplicationState,
+    user_id: i64,
+    login: String,
+    role: Role,
+    session_id: i64,
+) -> Result<String, ApiError> {
+    let claims = Claims {
+        exp: (Utc::now() + Duration::minutes(state.config.jwt.access_token_ttl_minutes)).timestamp()
+            as usize,
+        sub: login,
+        user_id,
+        session_id,
+        role,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(state.config.jwt.secret_key.as_ref()),
+    )
+    .map_err(|_| ApiError::InternalServerError)
+}
+
+async fn rehash_password(state: &ApplicationState, user_id: i64, password: String) {
+    let password_hash = match hash_password(password, state.config.password_hashing.clone()).await {
+        Ok(password_hash) => password_hash,
+        Err(err) => {
+            log::warn!("cannot rehash password of user {user_id}: {err}");
+            return;
+        }
+    };
+
+    if let Err(err) = sqlx::query!(
+        "update users set password_hash = $1 where id = $2",
+        password_hash,
+        user_id
+    )
+    .execute(&state.db_pool)
+    .await
+    {
+        log::warn!("cannot store rehashed password of user {user_id}: {err}");
     }
 }
 
 pub async fn get_user(
     Path(id): Path<i64>,
     State(state): State<ApplicationState>,
-) -> Result<(StatusCode, Json<UserDTO>), ApiError> {
-    let user = sqlx::query_as!(User, "select * from users where id = $1", id)
-        .fetch_optional(&state.db_pool)
-        .await
-        .map_err(|_| ApiError::InternalServerError)?;
+) -> Result<(StatusCode, Json<UserProfileDTO>), ApiError> {
+    let profile = find_user_profile(&state, id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("user not found".to_string()))?;
 
-    match user {
-        Some(user) => Ok((
-            StatusCode::OK,
-            Json(UserDTO {
-                id: user.id,
-                login: user.login,
-            }),
-        )),
-        None => Err(ApiError::NotFound("user not found".to_string())),
+    Ok((StatusCode::OK, Json(profile)))
+}
+
+pub async fn patch_me(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+    ValidatedJson(update_profile_dto): ValidatedJson<UpdateProfileDTO>,
+) -> Result<(StatusCode, Json<UserProfileDTO>), ApiError> {
+    ensure_can_post(&state, &claims).await?;
+
+    let display_name = update_profile_dto.display_name.as_deref().map(str::trim);
+    if display_name.is_some_and(|name| name.chars().any(char::is_control)) {
+        return Err(ApiError::BadRequest(
+            "display name must not contain control characters".to_string(),
+        ));
     }
+
+    let bio = update_profile_dto.bio.as_deref().map(str::trim);
+
+    sqlx::query!(
+        "
+        update users set
+            display_name = case when $2 then nullif($3, '') else display_name end,
+            bio = case when $4 then nullif($5, '') else bio end
+        where id = $1
+        ",
+        claims.user_id,
+        display_name.is_some(),
+        display_name,
+        bio.is_some(),
+        bio
+    )
+    .execute(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    let profile = find_user_profile(&state, claims.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("user not found".to_string()))?;
+
+    Ok((StatusCode::OK, Json(profile)))
 }
 
-pub async fn get_me(
+/// Avatars go through the same validation as attachments, but only images
+/// are accepted and only their thumbnail is kept.
+pub async fn upload_avatar(
     Extension(claims): Extension<Claims>,
     State(state): State<ApplicationState>,
-) -> Result<(StatusCode, Json<UserDTO>), ApiError> {
-    let user = sqlx::query_as!(User, "select * from users where id = $1", claims.user_id)
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<UserProfileDTO>), ApiError> {
+    ensure_can_post(&state, &claims).await?;
+
+    let mut upload = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|err| ApiError::BadRequest(err.body_text()))?
+    {
+        if field.name() == Some(AVATAR_FIELD) {
+            upload = Some(read_upload(field, &state.config.attachments).await?);
+            break;
+        }
+    }
+
+    let (_, upload) = upload
+        .ok_or_else(|| ApiError::BadRequest(format!("no file in the \"{AVATAR_FIELD}\" field")))?;
+    let image = upload
+        .image
+        .ok_or_else(|| ApiError::BadRequest("avatar must be an image".to_string()))?;
+
+    let avatar_key = generate_storage_key();
+    state
+        .storage
+        .put(&avatar_key, &image.thumbnail)
+        .await
+        .map_err(|err| {
+            log::warn!("cannot store an avatar: {err}");
+            ApiError::InternalServerError
+        })?;
+
+    let old_avatar_key = match replace_avatar_key(&state, claims.user_id, Some(&avatar_key)).await {
+        Ok(old_avatar_key) => old_avatar_key,
+        Err(err) => {
+            delete_stored_files(state.storage.as_ref(), [avatar_key]).await;
+            return Err(err);
+        }
+    };
+    delete_stored_files(state.storage.as_ref(), old_avatar_key).await;
+
+    let profile = find_user_profile(&state, claims.user_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("user not found".to_string()))?;
+
+    Ok((StatusCode::OK, Json(profile)))
+}
+
+pub async fn remove_avatar(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+) -> Result<StatusCode, ApiError> {
+    let old_avatar_key = replace_avatar_key(&state, claims.user_id, None).await?;
+    delete_stored_files(state.storage.as_ref(), old_avatar_key).await;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn get_user_avatar(
+    Path(id): Path<i64>,
+    State(state): State<ApplicationState>,
+) -> Result<Response, ApiError> {
+    let avatar_key = sqlx::query_scalar!("select avatar_key from users where id = $1", id)
         .fetch_optional(&state.db_pool)
+        .await
+        .map_err(|_| ApiError::InternalServerError)?
+        .flatten()
+        .ok_or_else(|| ApiError::NotFound("user has no avatar".to_string()))?;
+
+    stored_file_response(
+        state.storage.as_ref(),
+        &avatar_key,
+        "image/png".to_string(),
+        "avatar.png",
+    )
+    .await
+}
+
+pub async fn ensure_user_exists(state: &ApplicationState, id: i64) -> Result<(), ApiError> {
+    let user_exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM users WHERE id = $1) AS "exists!""#,
+        id
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if user_exists {
+        Ok(())
+    } else {
+        Err(ApiError::NotFound("user not found".to_string()))
+    }
+}
+
+/// Returns the key of the previous avatar, whose file is to be deleted.
+async fn replace_avatar_key(
+    state: &ApplicationState,
+    user_id: i64,
+    avatar_key: Option<&str>,
+) -> Result<Option<String>, ApiError> {
+    let mut tx = state
+        .db_pool
+        .begin()
+        .await
+        .map_err(|_| ApiError::InternalServerError)?;
+
+    let old_avatar_key = sqlx::query_scalar!(
+        "select avatar_key from users where id = $1 for update",
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .ok_or_else(|| ApiError::NotFound("user not found".to_string()))?;
+
+    sqlx::query!(
+        "update users set avatar_key = $2 where id = $1",
+        user_id,
+        avatar_key
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    tx.commit()
         .await
         .map_err(|_| ApiError::InternalServerError)?;
 
+    Ok(old_avatar_key)
+}
+
+async fn find_user_profile(
+    state: &ApplicationState,
+    id: i64,
+) -> Result<Option<UserProfileDTO>, ApiError> {
+    let user = sqlx::query!(
+        r#"
+        SELECT
+            u.id,
+            u.login,
+            u.display_name,
+            u.bio,
+            u.avatar_key,
+            u.created_at,
+            u.last_seen_at,
+            (SELECT COUNT(*) FROM topics t WHERE t.author_id = u.id AND t.deleted_at IS NULL)
+                AS "topics_count!",
+            (
+                SELECT COUNT(*)
+                FROM posts p
+                JOIN topics t ON p.topic_id = t.id
+                WHERE p.author_id = u.id AND p.deleted_at IS NULL AND t.deleted_at IS NULL
+            ) AS "posts_count!",
+            (
+                SELECT COUNT(*)
+                FROM reactions r
+                JOIN posts p ON r.post_id = p.id
+                WHERE p.author_id = u.id AND p.deleted_at IS NULL AND r.author_id <> u.id
+            ) AS "reactions_received!"
+        FROM users u
+        WHERE u.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(user.map(|user| UserProfileDTO {
+        id: user.id,
+        login: user.login,
+        display_name: user.display_name,
+        bio: user.bio,
+        avatar_url: user
+            .avatar_key
+            .map(|key| format!("/users/{}/avatar?v={}", user.id, &key[..8])),
+        registered_at: user.created_at,
+        last_seen_at: user.last_seen_at,
+        topics_count: user.topics_count,
+        posts_count: user.posts_count,
+        reactions_received: user.reactions_received,
+    }))
+}
+
+pub async fn get_me(
+    Extension(claims): Extension<Claims>,
+    State(state): State<ApplicationState>,
+) -> Result<(StatusCode, Json<CurrentUserDTO>), ApiError> {
+    let user = sqlx::query!(
+        r#"
+        SELECT
+            u.id,
+            u.login,
+            u.email,
+            u.email_verified_at IS NOT NULL AS "email_verified!",
+            (
+                SELECT COUNT(*)
+                FROM notifications n
+                JOIN topics t ON n.topic_id = t.id
+                JOIN posts p ON n.post_id = p.id
+                WHERE
+                    n.user_id = u.id
+                    AND n.read_at IS NULL
+                    AND t.deleted_at IS NULL
+                    AND p.deleted_at IS NULL
+            ) AS "unread_notifications!"
+        FROM users u
+        WHERE u.id = $1
+        "#,
+        claims.user_id
+    )
+    .fetch_optional(&state.db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
     match user {
         Some(user) => Ok((
             StatusCode::OK,
-            Json(UserDTO {
+            Json(CurrentUserDTO {
                 id: user.id,
                 login: user.login,
+                unread_notifications: user.unread_notifications,
+                email: user.email,
+                email_verified: user.email_verified,
             }),
         )),
         None => Err(ApiError::NotFound("user not found".to_string())),
@@ -0,0 +1,89 @@
+use std::time::Duration;
+
+use crate::{
+    login_protection::delete_expired_failures, state::ApplicationState,
+    uploads::delete_stored_files,
+};
+
+pub async fn purge_deleted_content(state: ApplicationState) {
+    let config = &state.config.soft_deletion;
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        config.purge_interval_minutes.max(1) * 60,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        // rows of attachments would be deleted by the cascade anyway, but the
+        // files have to be deleted from the storage explicitly
+        match sqlx::query!(
+            "
+            delete from attachments a
+            using posts p, topics t
+            where a.post_id = p.id and p.topic_id = t.id
+            and (
+                p.deleted_at < now() - make_interval(days => $1)
+                or t.deleted_at < now() - make_interval(days => $1)
+            )
+            returning a.storage_key, a.thumbnail_key
+            ",
+            config.retention_days as i32
+        )
+        .fetch_all(&state.db_pool)
+        .await
+        {
+            Ok(attachments) => {
+                let keys = attachments
+                    .into_iter()
+                    .flat_map(|attachment| [Some(attachment.storage_key), attachment.thumbnail_key])
+                    .flatten();
+                delete_stored_files(state.storage.as_ref(), keys).await;
+            }
+            Err(err) => {
+                log::warn!("cannot purge attachments of removed content: {err}");
+                continue;
+            }
+        }
+
+        let posts = sqlx::query!(
+            "delete from posts where deleted_at < now() - make_interval(days => $1)",
+            config.retention_days as i32
+        )
+        .execute(&state.db_pool)
+        .await;
+
+        let topics = sqlx::query!(
+            "delete from topics where deleted_at < now() - make_interval(days => $1)",
+            config.retention_days as i32
+        )
+        .execute(&state.db_pool)
+        .await;
+
+        match (posts, topics) {
+            (Ok(posts), Ok(topics)) => log::info!(
+                "purged {} removed posts and {} removed topics",
+                posts.rows_affected(),
+                topics.rows_affected()
+            ),
+            (Err(err), _) | (_, Err(err)) => log::warn!("cannot purge removed content: {err}"),
+        }
+    }
+}
+
+/// Keys of login failures are chosen by clients, so the table is cleaned
+/// regularly instead of growing with every guessed login.
+pub async fn delete_expired_login_failures(state: ApplicationState) {
+    let config = &state.config.login_protection;
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        config.failure_window_minutes.max(1) as u64 * 60,
+    ));
+
+    loop {
+        interval.tick().await;
+
+        match delete_expired_failures(&state.db_pool, config).await {
+            Ok(deleted) => log::info!("deleted {deleted} expired login failures"),
+            Err(err) => log::warn!("cannot delete expired login failures: {err}"),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgPool;
+
+use crate::{config::LoginProtectionConfig, dto::users::LoginAttemptsDTO, errors::ApiError};
+
+const LOGIN_SCOPE: &str = "login";
+const IP_SCOPE: &str = "ip";
+
+/// Fails while either the login or the client address is locked out; the
+/// password must not even be checked then.
+pub async fn ensure_login_allowed(db_pool: &PgPool, login: &str, ip: &str) -> Result<(), ApiError> {
+    let lockout = sqlx::query!(
+        r#"
+        SELECT failures, locked_until AS "locked_until!"
+        FROM login_failures
+        WHERE
+            ((scope = $1 AND key = $2) OR (scope = $3 AND key = $4))
+            AND locked_until > NOW()
+        ORDER BY locked_until DESC
+        LIMIT 1
+        "#,
+        LOGIN_SCOPE,
+        login,
+        IP_SCOPE,
+        ip
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    match lockout {
+        Some(lockout) => Err(ApiError::LoginLocked(LoginAttemptsDTO {
+            failed_attempts: lockout.failures,
+            attempts_left: 0,
+            locked_until: Some(lockout.locked_until),
+        })),
+        None => Ok(()),
+    }
+}
+
+/// Counts a failed login against both the login and the client address.
+/// The returned attempts are the ones of the login, while the lockout is the
+/// longest of the two.
+pub async fn record_failed_login(
+    db_pool: &PgPool,
+    config: &LoginProtectionConfig,
+    login: &str,
+    ip: &str,
+) -> Result<LoginAttemptsDTO, ApiError> {
+    let (login_failures, login_locked_until) = record_failure(
+        db_pool,
+        config,
+        LOGIN_SCOPE,
+        login,
+        config.max_failures_per_login,
+    )
+    .await?;
+    let (_, ip_locked_until) =
+        record_failure(db_pool, config, IP_SCOPE, ip, config.max_failures_per_ip).await?;
+
+    Ok(LoginAttemptsDTO {
+        failed_attempts: login_failures,
+        attempts_left: (config.max_failures_per_login - login_failures).max(0),
+        locked_until: login_locked_until.max(ip_locked_until),
+    })
+}
+
+/// Resets failures of the login; failures of the address are kept, otherwise
+/// a valid account would let anybody guess passwords of other ones.
+pub async fn record_successful_login(
+    db_pool: &PgPool,
+    user_id: i64,
+    login: &str,
+    ip: &str,
+) -> Result<(), ApiError> {
+    sqlx::query!(
+        "delete from login_failures where scope = $1 and key = $2",
+        LOGIN_SCOPE,
+        login
+    )
+    .execute(db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    sqlx::query!(
+        "update users set last_login_at = now(), last_login_ip = $2 where id = $1",
+        user_id,
+        ip
+    )
+    .execute(db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(())
+}
+
+/// Failures older than the window would be reset by the next failure anyway,
+/// so once their lockout is over they are only garbage.
+pub async fn delete_expired_failures(
+    db_pool: &PgPool,
+    config: &LoginProtectionConfig,
+) -> sqlx::Result<u64> {
+    let result = sqlx::query!(
+        "
+        delete from login_failures
+        where
+            last_failed_at < now() - make_interval(mins => $1)
+            and (locked_until is null or locked_until < now())
+        ",
+        config.failure_window_minutes
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+async fn record_failure(
+    db_pool: &PgPool,
+    config: &LoginProtectionConfig,
+    scope: &str,
+    key: &str,
+    max_failures: i32,
+) -> Result<(i32, Option<DateTime<Utc>>), ApiError> {
+    let failures = sqlx::query_scalar!(
+        "
+        insert into login_failures(scope, key, failures) values ($1, $2, 1)
+        on conflict (scope, key) do update set
+            failures = case
+                when login_failures.last_failed_at < now() - make_interval(mins => $3) then 1
+                else login_failures.failures + 1
+            end,
+            last_failed_at = now()
+        returning failures
+        ",
+        scope,
+        key,
+        config.failure_window_minutes
+    )
+    .fetch_one(db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    if failures < max_failures {
+        return Ok((failures, None));
+    }
+
+    // every failure after the limit doubles the lockout
+    let exponent = (failures - max_failures).min(20) as u32;
+    let lockout_seconds = config
+        .base_lockout_seconds
+        .saturating_mul(2_i64.pow(exponent))
+        .min(config.max_lockout_seconds);
+    let locked_until = Utc::now() + Duration::seconds(lockout_seconds);
+
+    sqlx::query!(
+        "update login_failures set locked_until = $3 where scope = $1 and key = $2",
+        scope,
+        key,
+        locked_until
+    )
+    .execute(db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok((failures, Some(locked_until)))
+}
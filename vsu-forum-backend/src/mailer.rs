@@ -0,0 +1,153 @@
+use std::{fmt::Debug, path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    Address, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use tokio::{
+    fs::OpenOptions,
+    io::{self, AsyncWriteExt},
+    sync::Mutex,
+};
+
+use crate::config::{MailerConfig, SmtpConfig};
+
+pub struct Mail {
+    /// A parsed address, so a login or another arbitrary string cannot be
+    /// passed as the recipient.
+    pub to: Address,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Debug + Send + Sync {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()>;
+}
+
+pub fn build_mailer(config: &MailerConfig) -> anyhow::Result<Arc<dyn Mailer>> {
+    Ok(match config {
+        MailerConfig::Log => Arc::new(LogMailer),
+        MailerConfig::Stdout => Arc::new(StdoutMailer),
+        MailerConfig::File { path } => Arc::new(FileMailer {
+            path: path.clone(),
+            write_lock: Mutex::new(()),
+        }),
+        MailerConfig::Smtp(smtp_config) => Arc::new(SmtpMailer::new(smtp_config)?),
+    })
+}
+
+fn format_mail(mail: &Mail) -> String {
+    format!(
+        "Date: {}\nTo: {}\nSubject: {}\n\n{}\n\n",
+        Utc::now().to_rfc2822(),
+        mail.to,
+        mail.subject,
+        mail.body
+    )
+}
+
+/// Does not deliver anything and logs only who would get which mail: bodies
+/// carry secret tokens, and logs are read by more people than mailboxes.
+#[derive(Debug)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()> {
+        log::info!("mail to {} not sent: {}", mail.to, mail.subject);
+        Ok(())
+    }
+}
+
+/// Prints mails as they would be sent, for running in a container.
+#[derive(Debug)]
+pub struct StdoutMailer;
+
+#[async_trait]
+impl Mailer for StdoutMailer {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()> {
+        let mut stdout = io::stdout();
+        stdout.write_all(format_mail(mail).as_bytes()).await?;
+        stdout.flush().await?;
+        Ok(())
+    }
+}
+
+/// Appends every mail to one file, one after another.
+#[derive(Debug)]
+pub struct FileMailer {
+    path: PathBuf,
+    /// Keeps mails sent at the same time from interleaving.
+    write_lock: Mutex<()>,
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()> {
+        let text = format_mail(mail);
+
+        let _guard = self.write_lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(text.as_bytes()).await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// Connections are opened lazily, so an unreachable relay is only
+    /// reported when the first mail is sent.
+    fn new(config: &SmtpConfig) -> anyhow::Result<Self> {
+        let mut builder = match (config.tls, config.port) {
+            (true, 465) => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            (true, _) => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+            (false, _) => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        }
+        .port(config.port);
+
+        if let Some(username) = &config.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                config.password.clone().unwrap_or_default(),
+            ));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config
+                .from
+                .parse()
+                .with_context(|| format!("invalid sender address {}", config.from))?,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: &Mail) -> anyhow::Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(Mailbox::new(None, mail.to.clone()))
+            .subject(&mail.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(mail.body.clone())?;
+
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
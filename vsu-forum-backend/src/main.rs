@@ -1,41 +1,76 @@
+mod attachments;
+mod audit;
 pub mod config;
 pub mod dto;
 mod errors;
+mod events;
 mod extractors;
 pub mod handlers;
+mod jobs;
+mod login_protection;
+mod mailer;
+mod mentions;
 mod middlewares;
 pub mod models;
+mod notifications;
+mod policies;
 pub mod state;
+mod storage;
 mod tools;
+mod uploads;
 
-use std::{env::var, path::Path, str::FromStr};
+use std::{env::var, net::SocketAddr, path::Path, str::FromStr};
 
 use anyhow::Context;
 use axum::{
-    routing::{delete, get, patch, post},
+    extract::DefaultBodyLimit,
+    routing::{delete, get, patch, post, put},
     Router,
 };
 use env_logger::{Builder, Target};
 use log::LevelFilter;
+use middlewares::rate_limit::RateLimiter;
+use models::Role;
 use sqlx::{migrate::Migrator, postgres::PgPoolOptions};
 use tower_http::cors::CorsLayer;
 
 use handlers::{
+    account::{
+        change_email, change_password, delete_account, request_password_reset, reset_password,
+        verify_email,
+    },
+    attachments::{
+        get_attachment, get_attachment_thumbnail, remove_attachment, upload_attachments,
+    },
+    audit_log::get_audit_log,
     available_reactions::{
         create_available_reaction, delete_available_reaction, get_available_reactions,
         patch_available_reaction,
     },
     bookmarks::{create_bookmark, get_bookmarks, remove_bookmark},
-    posts::{create_post, get_post, get_posts, patch_post, remove_post},
+    notifications::{get_notifications, read_all_notifications, read_notification},
+    post_revisions::{get_post_revisions, get_post_revisions_diff},
+    posts::{
+        create_post, get_post, get_posts, get_posts_tree, get_user_mentions, get_user_posts,
+        patch_post, remove_post, restore_post,
+    },
     reactions::{add_reaction, get_reactions, remove_reaction},
     reports::{create_report, get_report, get_reports, patch_report, remove_report},
+    restrictions::{create_user_restriction, get_user_restrictions, revoke_user_restriction},
     search::search,
     stats::get_stats,
-    topics::{create_topic, get_topic, get_topics, patch_topic, remove_topic},
+    topic_events::get_topic_events,
+    topics::{
+        create_topic, get_topic, get_topics, get_user_topics, patch_topic, remove_topic,
+        restore_topic,
+    },
     topics_categories::{
         create_topic_category, get_topic_categories, get_topic_category, remove_topic_category,
     },
-    users::{get_me, get_user, login_user, register_user},
+    users::{
+        get_me, get_user, get_user_avatar, login_user, logout_user, logout_user_everywhere,
+        patch_me, refresh_tokens, register_user, remove_avatar, upload_avatar,
+    },
 };
 use state::ApplicationState;
 
@@ -76,52 +111,132 @@ async fn main() -> anyhow::Result<()> {
     let state = ApplicationState {
         config: config.clone(),
         db_pool: db_pool.clone(),
+        topic_events: events::TopicEventsHub::new(),
+        storage: storage::build_storage(&config.storage)
+            .await
+            .context("cannot initialize file storage")?,
+        mailer: mailer::build_mailer(&config.mailer).context("cannot initialize mailer")?,
     };
 
-    let router = Router::new()
+    // all files of a post may be sent in one request, with some room for the
+    // multipart boundaries and headers
+    let attachments_body_limit = config.attachments.max_file_size_bytes
+        * config.attachments.max_files_per_post.max(1) as usize
+        + 64 * 1024;
+
+    tokio::spawn(jobs::purge_deleted_content(state.clone()));
+    tokio::spawn(jobs::delete_expired_login_failures(state.clone()));
+
+    let ip_limited_router = Router::new()
         .route("/users/register", post(register_user))
         .route("/users/login", post(login_user))
+        .route("/users/password-reset", post(request_password_reset))
+        .route("/users/password-reset/confirm", post(reset_password))
+        .route("/users/email-verification/confirm", post(verify_email))
+        .route_layer(axum::middleware::from_fn_with_state(
+            RateLimiter::new(config.rate_limit.enabled, &config.rate_limit.ip),
+            middlewares::rate_limit::rate_limit_by_ip,
+        ));
+
+    let router = Router::new()
+        .merge(ip_limited_router)
+        .route("/users/refresh", post(refresh_tokens))
         .route("/users/:id", get(get_user))
+        .route("/users/:id/posts", get(get_user_posts))
+        .route("/users/:id/topics", get(get_user_topics))
+        .route("/users/:id/mentions", get(get_user_mentions))
+        .route("/users/:id/avatar", get(get_user_avatar))
         .route("/topics", get(get_topics))
         .route("/available-reactions", get(get_available_reactions))
         .route("/topics/:id", get(get_topic))
+        .route("/topics/:id/posts/tree", get(get_posts_tree))
+        .route("/topics/:id/events", get(get_topic_events))
         .route("/topics-categories", get(get_topic_categories))
         .route("/topics-categories/:id", get(get_topic_category))
         .route("/posts", get(get_posts))
         .route("/posts/:id", get(get_post))
         .route("/posts/:post_id/reactions", get(get_reactions))
-        .route("/reports", get(get_reports))
-        .route("/reports/:id", get(get_report))
+        .route("/attachments/:id", get(get_attachment))
+        .route("/attachments/:id/thumbnail", get(get_attachment_thumbnail))
         .route("/search", get(search))
         .route("/stats", get(get_stats));
 
-    let secure_router = Router::new()
-        .route("/users/me", get(get_me))
+    let moderator_router = Router::new()
+        .route("/posts/:id/revisions", get(get_post_revisions))
+        .route("/posts/:id/revisions/diff", get(get_post_revisions_diff))
+        .route("/posts/:id/restore", post(restore_post))
+        .route("/topics/:id/restore", post(restore_topic))
+        .route("/users/:id/restrictions", get(get_user_restrictions))
+        .route("/users/:id/restrictions", post(create_user_restriction))
+        .route("/restrictions/:id", delete(revoke_user_restriction))
+        .route("/reports", get(get_reports))
+        .route("/reports/:id", get(get_report))
+        .route("/reports/:id", delete(remove_report))
+        .route("/reports/:id", patch(patch_report))
+        .route_layer(axum::middleware::from_fn_with_state(
+            Role::Moderator,
+            middlewares::roles::require_role,
+        ));
+
+    let admin_router = Router::new()
+        .route("/audit-log", get(get_audit_log))
         .route("/available-reactions", post(create_available_reaction))
         .route(
             "/available-reactions/:id",
             delete(delete_available_reaction),
         )
         .route("/available-reactions/:id", patch(patch_available_reaction))
+        .route("/topics-categories", post(create_topic_category))
+        .route("/topics-categories/:id", delete(remove_topic_category))
+        .route_layer(axum::middleware::from_fn_with_state(
+            Role::Admin,
+            middlewares::roles::require_role,
+        ));
+
+    let secure_router = Router::new()
+        .route("/users/me", get(get_me))
+        .route("/users/me", patch(patch_me))
+        .route("/users/me", delete(delete_account))
+        .route("/users/me/password", post(change_password))
+        .route("/users/me/email", put(change_email))
+        .route(
+            "/users/me/avatar",
+            put(upload_avatar).layer(DefaultBodyLimit::max(
+                config.attachments.max_file_size_bytes + 64 * 1024,
+            )),
+        )
+        .route("/users/me/avatar", delete(remove_avatar))
+        .route("/users/logout", post(logout_user))
+        .route("/users/logout-all", post(logout_user_everywhere))
         .route("/topics", post(create_topic))
         .route("/topics/:id", delete(remove_topic))
         .route("/topics/:id", patch(patch_topic))
+        .route("/notifications", get(get_notifications))
+        .route("/notifications/read-all", post(read_all_notifications))
+        .route("/notifications/:id/read", post(read_notification))
         .route("/bookmarks", get(get_bookmarks))
         .route("/topics/:topic_id/bookmark", post(create_bookmark))
         .route("/topics/:topic_id/bookmark", delete(remove_bookmark))
-        .route("/topics-categories", post(create_topic_category))
-        .route("/topics-categories/:id", delete(remove_topic_category))
         .route("/posts", post(create_post))
         .route("/posts/:id", delete(remove_post))
         .route("/posts/:id", patch(patch_post))
+        .route(
+            "/posts/:id/attachments",
+            post(upload_attachments).layer(DefaultBodyLimit::max(attachments_body_limit)),
+        )
+        .route("/attachments/:id", delete(remove_attachment))
         .route("/reports", post(create_report))
-        .route("/reports/:id", delete(remove_report))
-        .route("/reports/:id", patch(patch_report))
         .route("/posts/:post_id/reactions/:reaction", post(add_reaction))
         .route(
             "/posts/:post_id/reactions/:reaction",
             delete(remove_reaction),
         )
+        .merge(moderator_router)
+        .merge(admin_router)
+        .route_layer(axum::middleware::from_fn_with_state(
+            RateLimiter::new(config.rate_limit.enabled, &config.rate_limit.user),
+            middlewares::rate_limit::rate_limit_by_user,
+        ))
         .route_layer(axum::middleware::from_fn_with_state(
             state.clone(),
             middlewares::auth::auth_middleware,
@@ -137,9 +252,12 @@ async fn main() -> anyhow::Result<()> {
         .await
         .context("failed to bind TCP listener")?;
 
-    axum::serve(listener, app)
-        .await
-        .context("axum::serve failed")?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .context("axum::serve failed")?;
 
     Ok(())
 }
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use sqlx::{PgConnection, PgPool};
+
+use crate::{
+    dto::{posts::PostDTO, users::UserDTO},
+    tools::extract_mentions,
+};
+
+/// Replaces the mentions of the post with the users mentioned in `text`;
+/// unknown logins are ignored. Returns ids of the users who were not mentioned
+/// in the post before.
+pub async fn store_post_mentions(
+    conn: &mut PgConnection,
+    post_id: i64,
+    text: &str,
+) -> Result<Vec<i64>, sqlx::Error> {
+    let logins = extract_mentions(text);
+
+    sqlx::query!(
+        "
+        delete from post_mentions
+        where post_id = $1
+        and user_id not in (select id from users where login = any($2) and deleted_at is null)
+        ",
+        post_id,
+        &logins
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if logins.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_scalar!(
+        "
+        insert into post_mentions(post_id, user_id)
+        select $1, id from users where login = any($2) and deleted_at is null
+        on conflict do nothing
+        returning user_id
+        ",
+        post_id,
+        &logins
+    )
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Fills `mentions` of the posts. Removed posts show no text, so their
+/// mentions are not shown either.
+pub async fn attach_mentions<'a>(
+    db_pool: &PgPool,
+    posts: impl IntoIterator<Item = &'a mut PostDTO>,
+) -> Result<(), sqlx::Error> {
+    let posts: Vec<&mut PostDTO> = posts
+        .into_iter()
+        .filter(|post| post.deleted_at.is_none())
+        .collect();
+    if posts.is_empty() {
+        return Ok(());
+    }
+
+    let post_ids: Vec<i64> = posts.iter().map(|post| post.id).collect();
+    let mut mentions: HashMap<i64, Vec<UserDTO>> = HashMap::new();
+    sqlx::query!(
+        "
+        SELECT pm.post_id, u.id, u.login
+        FROM post_mentions pm
+        JOIN users u ON u.id = pm.user_id
+        WHERE pm.post_id = any($1)
+        ORDER BY u.login
+        ",
+        &post_ids
+    )
+    .fetch_all(db_pool)
+    .await?
+    .into_iter()
+    .for_each(|record| {
+        mentions.entry(record.post_id).or_default().push(UserDTO {
+            id: record.id,
+            login: record.login,
+        })
+    });
+
+    for post in posts {
+        if let Some(post_mentions) = mentions.remove(&post.id) {
+            post.mentions = post_mentions;
+        }
+    }
+
+    Ok(())
+}
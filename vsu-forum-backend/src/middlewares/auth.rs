@@ -9,6 +9,8 @@ use jsonwebtoken::{decode, DecodingKey, Validation};
 
 use crate::{
     dto::{claims::Claims, errors::ErrorDTO},
+    models::RestrictionKind,
+    policies::ensure_not_restricted,
     ApplicationState,
 };
 
@@ -34,7 +36,15 @@ pub async fn auth_middleware(
             .into_response();
     };
 
-    if let Some(claims) = authorize_current_user(auth_header, &state.config.jwt.secret_key).await {
+    if let Some(claims) = authorize_current_user(auth_header, &state).await {
+        if let Err(err) =
+            ensure_not_restricted(&state.db_pool, claims.user_id, RestrictionKind::Ban).await
+        {
+            return err.into_response();
+        }
+
+        touch_last_seen(&state, claims.user_id).await;
+
         req.extensions_mut().insert(claims);
         next.run(req).await.into_response()
     } else {
@@ -48,12 +58,46 @@ pub async fn auth_middleware(
     }
 }
 
-async fn authorize_current_user(auth_token: String, secret_key: &str) -> Option<Claims> {
-    decode::<Claims>(
+async fn authorize_current_user(auth_token: String, state: &ApplicationState) -> Option<Claims> {
+    let claims = decode::<Claims>(
         &auth_token,
-        &DecodingKey::from_secret(secret_key.as_ref()),
+        &DecodingKey::from_secret(state.config.jwt.secret_key.as_ref()),
         &Validation::default(),
     )
     .ok()
-    .map(|d| d.claims)
+    .map(|d| d.claims)?;
+
+    // access tokens are short-lived, but they still must die with their session
+    let session_active = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM sessions
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        ) AS "active!"
+        "#,
+        claims.session_id,
+        claims.user_id
+    )
+    .fetch_one(&state.db_pool)
+    .await
+    .ok()?;
+
+    session_active.then_some(claims)
+}
+
+/// Last-seen time is precise to a minute, so most requests do not write to the
+/// database.
+async fn touch_last_seen(state: &ApplicationState, user_id: i64) {
+    if let Err(err) = sqlx::query!(
+        "
+        update users set last_seen_at = now()
+        where id = $1 and (last_seen_at is null or last_seen_at < now() - interval '1 minute')
+        ",
+        user_id
+    )
+    .execute(&state.db_pool)
+    .await
+    {
+        log::warn!("cannot update last seen time of user {user_id}: {err}");
+    }
 }
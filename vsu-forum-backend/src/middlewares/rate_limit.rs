@@ -0,0 +1,248 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+
+use crate::{
+    config::TokenBucketConfig,
+    dto::{claims::Claims, errors::ErrorDTO},
+};
+
+/// Buckets of inactive keys are dropped once this many keys are tracked; new
+/// keys are rejected while none of the tracked ones can be dropped.
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// In-process token bucket per key; every request takes one token, tokens
+/// are refilled continuously up to the bucket capacity.
+pub struct RateLimiter<K> {
+    enabled: bool,
+    capacity: f64,
+    refill_per_second: f64,
+    buckets: Mutex<Buckets<K>>,
+}
+
+struct Buckets<K> {
+    by_key: HashMap<K, Bucket>,
+    /// Sweeps are not repeated until every bucket left after the previous one
+    /// could be refilled, so a full table is not scanned on every request.
+    next_sweep_at: Instant,
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    pub fn new(enabled: bool, config: &TokenBucketConfig) -> Arc<Self> {
+        Arc::new(Self {
+            enabled,
+            capacity: config.capacity.max(1) as f64,
+            refill_per_second: config.refill_per_minute.max(1) as f64 / 60.0,
+            buckets: Mutex::new(Buckets {
+                by_key: HashMap::new(),
+                next_sweep_at: Instant::now(),
+            }),
+        })
+    }
+
+    /// Takes a token of `key`, otherwise returns how long to wait for one.
+    fn try_acquire(&self, key: K) -> Result<(), Duration> {
+        if !self.enabled {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if buckets.by_key.len() >= MAX_TRACKED_KEYS && !buckets.by_key.contains_key(&key) {
+            if now >= buckets.next_sweep_at {
+                buckets
+                    .by_key
+                    .retain(|_, bucket| self.refilled_tokens(bucket, now) < self.capacity);
+                buckets.next_sweep_at =
+                    now + Duration::from_secs_f64(self.capacity / self.refill_per_second);
+            }
+
+            if buckets.by_key.len() >= MAX_TRACKED_KEYS {
+                return Err(buckets.next_sweep_at.duration_since(now));
+            }
+        }
+
+        let bucket = buckets.by_key.entry(key).or_insert(Bucket {
+            tokens: self.capacity,
+            updated_at: now,
+        });
+        bucket.tokens = self.refilled_tokens(bucket, now);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / self.refill_per_second,
+            ))
+        }
+    }
+
+    fn refilled_tokens(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        (bucket.tokens + elapsed * self.refill_per_second).min(self.capacity)
+    }
+}
+
+/// Limits write requests of authorized users. Must be layered inside
+/// `auth_middleware`, because it relies on the claims put there.
+pub async fn rate_limit_by_user(
+    State(limiter): State<Arc<RateLimiter<i64>>>,
+    Extension(claims): Extension<Claims>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if is_read_only(req.method()) {
+        return next.run(req).await;
+    }
+
+    match limiter.try_acquire(claims.user_id) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+/// Limits requests by the address of the connected peer, so behind a reverse
+/// proxy all clients share one bucket.
+pub async fn rate_limit_by_ip(
+    State(limiter): State<Arc<RateLimiter<IpAddr>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    match limiter.try_acquire(addr.ip()) {
+        Ok(()) => next.run(req).await,
+        Err(retry_after) => too_many_requests(retry_after),
+    }
+}
+
+fn is_read_only(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let retry_after_secs = retry_after.as_secs_f64().ceil() as u64;
+
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, retry_after_secs.to_string())],
+        Json(ErrorDTO {
+            err: format!("too many requests, retry in {retry_after_secs} seconds"),
+        }),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(enabled: bool) -> Arc<RateLimiter<i64>> {
+        RateLimiter::new(
+            enabled,
+            &TokenBucketConfig {
+                capacity: 2,
+                refill_per_minute: 6,
+            },
+        )
+    }
+
+    #[test]
+    fn burst_is_limited_by_capacity() {
+        let limiter = limiter(true);
+
+        assert!(limiter.try_acquire(1).is_ok());
+        assert!(limiter.try_acquire(1).is_ok());
+
+        let retry_after = limiter.try_acquire(1).unwrap_err();
+        assert!(retry_after > Duration::from_secs(9) && retry_after <= Duration::from_secs(10));
+    }
+
+    #[test]
+    fn keys_have_own_buckets() {
+        let limiter = limiter(true);
+
+        assert!(limiter.try_acquire(1).is_ok());
+        assert!(limiter.try_acquire(1).is_ok());
+        assert!(limiter.try_acquire(2).is_ok());
+    }
+
+    #[test]
+    fn disabled_limiter_lets_everything_through() {
+        let limiter = limiter(false);
+
+        assert!((0..10).all(|_| limiter.try_acquire(1).is_ok()));
+    }
+
+    #[test]
+    fn tokens_are_refilled_up_to_capacity() {
+        let limiter = limiter(true);
+        let now = Instant::now();
+        let bucket = Bucket {
+            tokens: 0.0,
+            updated_at: now,
+        };
+
+        assert_eq!(
+            limiter.refilled_tokens(&bucket, now + Duration::from_secs(5)),
+            0.5
+        );
+        assert_eq!(
+            limiter.refilled_tokens(&bucket, now + Duration::from_secs(60)),
+            2.0
+        );
+    }
+
+    #[test]
+    fn idle_buckets_are_swept_once_table_is_full() {
+        let limiter = limiter(true);
+        for key in 0..MAX_TRACKED_KEYS as i64 {
+            assert!(limiter.try_acquire(key).is_ok());
+        }
+
+        // as if the refill period has passed
+        for bucket in limiter.buckets.lock().unwrap().by_key.values_mut() {
+            bucket.tokens = 2.0;
+        }
+        assert!(limiter.try_acquire(-1).is_ok());
+
+        assert_eq!(limiter.buckets.lock().unwrap().by_key.len(), 1);
+    }
+
+    #[test]
+    fn new_keys_are_rejected_while_table_is_full() {
+        let limiter = limiter(true);
+        for key in 0..MAX_TRACKED_KEYS as i64 {
+            assert!(limiter.try_acquire(key).is_ok());
+        }
+
+        // nothing to sweep, all buckets are in use
+        assert!(limiter.try_acquire(-1).is_err());
+        assert!(limiter.try_acquire(-2).is_err());
+        assert!(limiter.try_acquire(0).is_ok());
+
+        assert_eq!(
+            limiter.buckets.lock().unwrap().by_key.len(),
+            MAX_TRACKED_KEYS
+        );
+    }
+}
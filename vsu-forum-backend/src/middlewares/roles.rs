@@ -0,0 +1,27 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension,
+};
+
+use crate::{dto::claims::Claims, errors::ApiError, models::Role};
+
+/// Must be layered inside `auth_middleware`, because it relies on the claims
+/// that are put into the request extensions there.
+pub async fn require_role(
+    State(required_role): State<Role>,
+    Extension(claims): Extension<Claims>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if claims.role >= required_role {
+        next.run(req).await
+    } else {
+        ApiError::Forbidden(format!(
+            "this action requires {} role",
+            required_role.as_str()
+        ))
+        .into_response()
+    }
+}
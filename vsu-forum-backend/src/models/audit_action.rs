@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    RemoveTopic,
+    RemovePost,
+    RemoveAttachment,
+    RemoveTopicCategory,
+    DeleteAvailableReaction,
+    RestrictUser,
+    RevokeUserRestriction,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::RemoveTopic => "remove_topic",
+            AuditAction::RemovePost => "remove_post",
+            AuditAction::RemoveAttachment => "remove_attachment",
+            AuditAction::RemoveTopicCategory => "remove_topic_category",
+            AuditAction::DeleteAvailableReaction => "delete_available_reaction",
+            AuditAction::RestrictUser => "restrict_user",
+            AuditAction::RevokeUserRestriction => "revoke_user_restriction",
+        }
+    }
+
+    /// Kind of the object whose id is stored in `target_id`.
+    pub fn target_type(&self) -> &'static str {
+        match self {
+            AuditAction::RemoveTopic => "topic",
+            AuditAction::RemovePost => "post",
+            AuditAction::RemoveAttachment => "attachment",
+            AuditAction::RemoveTopicCategory => "topic_category",
+            AuditAction::DeleteAvailableReaction => "available_reaction",
+            AuditAction::RestrictUser | AuditAction::RevokeUserRestriction => "user",
+        }
+    }
+}
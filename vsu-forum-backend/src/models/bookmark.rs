@@ -1,7 +1,9 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Bookmark {
     pub user_id: i64,
     pub topic_id: i64,
+    pub created_at: DateTime<Utc>,
 }
@@ -1,17 +1,41 @@
+pub mod attachment;
+pub mod audit_action;
+pub mod audit_log_entry;
 pub mod available_reaction;
 pub mod bookmark;
+pub mod notification;
+pub mod notification_kind;
 pub mod post;
+pub mod post_revision;
 pub mod reaction;
 pub mod report;
+pub mod report_status;
+pub mod report_target_type;
+pub mod restriction_kind;
+pub mod role;
+pub mod session;
 pub mod topic;
 pub mod topic_category;
 pub mod user;
+pub mod user_restriction;
 
+pub use attachment::Attachment;
+pub use audit_action::AuditAction;
+pub use audit_log_entry::AuditLogEntry;
 pub use available_reaction::AvailableReaction;
 pub use bookmark::Bookmark;
+pub use notification::Notification;
+pub use notification_kind::NotificationKind;
 pub use post::Post;
+pub use post_revision::PostRevision;
 pub use reaction::Reaction;
 pub use report::Report;
+pub use report_status::ReportStatus;
+pub use report_target_type::ReportTargetType;
+pub use restriction_kind::RestrictionKind;
+pub use role::Role;
+pub use session::Session;
 pub use topic::Topic;
 pub use topic_category::TopicCategory;
 pub use user::User;
+pub use user_restriction::UserRestriction;
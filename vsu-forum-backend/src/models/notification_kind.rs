@@ -0,0 +1,40 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// When one post concerns a user in several ways, they get a single
+/// notification of the most specific kind: reply, then mention, then post in
+/// a bookmarked topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    BookmarkedTopicPost,
+    Mention,
+    Reply,
+    Reaction,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::BookmarkedTopicPost => "bookmarked_topic_post",
+            NotificationKind::Mention => "mention",
+            NotificationKind::Reply => "reply",
+            NotificationKind::Reaction => "reaction",
+        }
+    }
+}
+
+impl FromStr for NotificationKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bookmarked_topic_post" => Ok(NotificationKind::BookmarkedTopicPost),
+            "mention" => Ok(NotificationKind::Mention),
+            "reply" => Ok(NotificationKind::Reply),
+            "reaction" => Ok(NotificationKind::Reaction),
+            _ => Err(anyhow::anyhow!("unknown notification kind: {s}")),
+        }
+    }
+}
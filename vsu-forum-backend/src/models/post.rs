@@ -7,5 +7,9 @@ pub struct Post {
     pub topic_id: i64,
     pub author_id: i64,
     pub text: String,
+    pub reply_to_post_id: Option<i64>,
     pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<i64>,
 }
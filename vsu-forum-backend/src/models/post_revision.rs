@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PostRevision {
+    pub id: i64,
+    pub post_id: i64,
+    pub editor_id: Option<i64>,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -6,4 +7,14 @@ pub struct Report {
     pub reported_user_id: i64,
     pub author_id: i64,
     pub reason: String,
+    pub created_at: DateTime<Utc>,
+    pub status: String,
+    pub assignee_id: Option<i64>,
+    pub resolution_note: Option<String>,
+    pub updated_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub target_type: String,
+    pub post_id: Option<i64>,
+    pub topic_id: Option<i64>,
+    pub content_snapshot: Option<String>,
 }
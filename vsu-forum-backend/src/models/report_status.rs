@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    #[default]
+    Open,
+    InReview,
+    Resolved,
+    Rejected,
+}
+
+impl ReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::InReview => "in_review",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Rejected => "rejected",
+        }
+    }
+
+    /// Resolved and rejected reports are closed: they get `resolved_at` and
+    /// must carry a resolution note.
+    pub fn is_closed(&self) -> bool {
+        matches!(self, ReportStatus::Resolved | ReportStatus::Rejected)
+    }
+}
+
+impl FromStr for ReportStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(ReportStatus::Open),
+            "in_review" => Ok(ReportStatus::InReview),
+            "resolved" => Ok(ReportStatus::Resolved),
+            "rejected" => Ok(ReportStatus::Rejected),
+            _ => Err(anyhow::anyhow!("unknown report status: {s}")),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportTargetType {
+    #[default]
+    User,
+    Post,
+    Topic,
+}
+
+impl ReportTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportTargetType::User => "user",
+            ReportTargetType::Post => "post",
+            ReportTargetType::Topic => "topic",
+        }
+    }
+}
+
+impl FromStr for ReportTargetType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(ReportTargetType::User),
+            "post" => Ok(ReportTargetType::Post),
+            "topic" => Ok(ReportTargetType::Topic),
+            _ => Err(anyhow::anyhow!("unknown report target type: {s}")),
+        }
+    }
+}
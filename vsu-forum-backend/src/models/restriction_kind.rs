@@ -0,0 +1,33 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// A banned user cannot use the authorized API at all, a muted one can only
+/// read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestrictionKind {
+    Ban,
+    Mute,
+}
+
+impl RestrictionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestrictionKind::Ban => "ban",
+            RestrictionKind::Mute => "mute",
+        }
+    }
+}
+
+impl FromStr for RestrictionKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ban" => Ok(RestrictionKind::Ban),
+            "mute" => Ok(RestrictionKind::Mute),
+            _ => Err(anyhow::anyhow!("unknown restriction kind: {s}")),
+        }
+    }
+}
@@ -8,4 +8,6 @@ pub struct Topic {
     pub category_id: i64,
     pub name: String,
     pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub deleted_by: Option<i64>,
 }
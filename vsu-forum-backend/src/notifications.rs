@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use sqlx::PgPool;
+
+use crate::models::NotificationKind;
+
+/// Notifies users who bookmarked the topic of the post, the author of the
+/// replied post and mentioned users; the author of the post is never notified.
+pub async fn notify_about_post(db_pool: &PgPool, post_id: i64) -> Result<(), sqlx::Error> {
+    let post = sqlx::query!(
+        r#"
+        SELECT p.author_id, p.topic_id, parent.author_id AS "parent_author_id?"
+        FROM posts p
+        LEFT JOIN posts parent ON parent.id = p.reply_to_post_id
+        WHERE p.id = $1
+        "#,
+        post_id
+    )
+    .fetch_one(db_pool)
+    .await?;
+
+    let mut recipients: HashMap<i64, NotificationKind> = HashMap::new();
+    let mut notify = |user_id: i64, kind: NotificationKind| {
+        let current_kind = recipients.entry(user_id).or_insert(kind);
+        *current_kind = (*current_kind).max(kind);
+    };
+
+    let bookmarked_by = sqlx::query_scalar!(
+        "select user_id from bookmarks where topic_id = $1",
+        post.topic_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    for user_id in bookmarked_by {
+        notify(user_id, NotificationKind::BookmarkedTopicPost);
+    }
+
+    let mentioned = sqlx::query_scalar!(
+        "select user_id from post_mentions where post_id = $1",
+        post_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    for user_id in mentioned {
+        notify(user_id, NotificationKind::Mention);
+    }
+
+    if let Some(parent_author_id) = post.parent_author_id {
+        notify(parent_author_id, NotificationKind::Reply);
+    }
+
+    recipients.remove(&post.author_id);
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    let (user_ids, kinds): (Vec<i64>, Vec<&str>) = recipients
+        .into_iter()
+        .map(|(user_id, kind)| (user_id, kind.as_str()))
+        .unzip();
+
+    sqlx::query!(
+        "
+        insert into notifications(user_id, kind, actor_id, topic_id, post_id)
+        select recipient.user_id, recipient.kind, $3, $4, $5
+        from unnest($1::bigint[], $2::text[]) as recipient(user_id, kind)
+        ",
+        &user_ids,
+        &kinds as &[&str],
+        post.author_id,
+        post.topic_id,
+        post_id
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Notifies users who were mentioned in the post when it was edited; the
+/// author of the post is never notified.
+pub async fn notify_about_mentions(
+    db_pool: &PgPool,
+    post_id: i64,
+    user_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    if user_ids.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query!(
+        "
+        insert into notifications(user_id, kind, actor_id, topic_id, post_id)
+        select mentioned.user_id, $3, p.author_id, p.topic_id, p.id
+        from posts p
+        cross join unnest($2::bigint[]) as mentioned(user_id)
+        where p.id = $1 and mentioned.user_id <> p.author_id
+        ",
+        post_id,
+        user_ids,
+        NotificationKind::Mention.as_str()
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Notifies the author of the post, unless they reacted to it themselves or
+/// were already notified about the same reaction of the same user.
+pub async fn notify_about_reaction(
+    db_pool: &PgPool,
+    post_id: i64,
+    reaction_id: i64,
+    actor_id: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "
+        insert into notifications(user_id, kind, actor_id, topic_id, post_id, reaction_id)
+        select author_id, $4, $2, topic_id, id, $3
+        from posts
+        where id = $1 and author_id <> $2
+        on conflict (user_id, post_id, actor_id, reaction_id) where kind = 'reaction'
+        do nothing
+        ",
+        post_id,
+        actor_id,
+        reaction_id,
+        NotificationKind::Reaction.as_str()
+    )
+    .execute(db_pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use sqlx::PgPool;
+
+use crate::{
+    config::EmailVerificationConfig,
+    dto::{claims::Claims, restrictions::RestrictionDTO, users::UserDTO},
+    errors::ApiError,
+    models::{RestrictionKind, Role},
+    state::ApplicationState,
+};
+
+/// Authors may edit and delete their own content, moderators may do it with
+/// anybody's content.
+pub fn ensure_can_modify(claims: &Claims, author_id: i64) -> Result<(), ApiError> {
+    if claims.user_id == author_id || claims.role >= Role::Moderator {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "you can modify only your own content".to_string(),
+        ))
+    }
+}
+
+/// Fails with the active restriction of the given kind that lasts the longest,
+/// if the user has any.
+pub async fn ensure_not_restricted(
+    db_pool: &PgPool,
+    user_id: i64,
+    kind: RestrictionKind,
+) -> Result<(), ApiError> {
+    let restriction = sqlx::query!(
+        r#"
+        SELECT
+            r.id, r.user_id, r.reason, r.created_at, r.expires_at, r.revoked_at,
+            u.id as "issuer_id?", u.login as "issuer_login?"
+        FROM user_restrictions r
+        LEFT JOIN users u ON r.issued_by = u.id
+        WHERE
+            r.user_id = $1
+            AND r.kind = $2
+            AND r.revoked_at IS NULL
+            AND (r.expires_at IS NULL OR r.expires_at > NOW())
+        ORDER BY r.expires_at DESC NULLS FIRST
+        LIMIT 1
+        "#,
+        user_id,
+        kind.as_str()
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    match restriction {
+        Some(r) => Err(ApiError::Restricted(RestrictionDTO {
+            id: r.id,
+            user_id: r.user_id,
+            kind,
+            reason: r.reason,
+            issued_by: r
+                .issuer_id
+                .zip(r.issuer_login)
+                .map(|(id, login)| UserDTO { id, login }),
+            created_at: r.created_at,
+            expires_at: r.expires_at,
+            revoked_at: r.revoked_at,
+        })),
+        None => Ok(()),
+    }
+}
+
+/// Checks that the user may create and change content: muted users and users
+/// who must confirm their e-mail address first can only read. Every handler
+/// that writes user content calls it.
+pub async fn ensure_can_post(state: &ApplicationState, claims: &Claims) -> Result<(), ApiError> {
+    ensure_not_restricted(&state.db_pool, claims.user_id, RestrictionKind::Mute).await?;
+    ensure_email_verified(&state.db_pool, &state.config.email_verification, claims).await
+}
+
+/// When the forum requires verified e-mail addresses, other users can only
+/// read until they confirm theirs. Moderators and admins are never limited.
+async fn ensure_email_verified(
+    db_pool: &PgPool,
+    config: &EmailVerificationConfig,
+    claims: &Claims,
+) -> Result<(), ApiError> {
+    if !config.require_verified_email || claims.role >= Role::Moderator {
+        return Ok(());
+    }
+
+    let verified = sqlx::query_scalar!(
+        r#"SELECT email_verified_at IS NOT NULL AS "verified!" FROM users WHERE id = $1"#,
+        claims.user_id
+    )
+    .fetch_optional(db_pool)
+    .await
+    .map_err(|_| ApiError::InternalServerError)?
+    .unwrap_or(false);
+
+    if verified {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "confirm your e-mail address to post".to_string(),
+        ))
+    }
+}
@@ -1,9 +1,14 @@
+use std::sync::Arc;
+
 use sqlx::PgPool;
 
-use crate::config::AppConfig;
+use crate::{config::AppConfig, events::TopicEventsHub, mailer::Mailer, storage::Storage};
 
 #[derive(Debug, Clone)]
 pub struct ApplicationState {
     pub config: AppConfig,
     pub db_pool: PgPool,
+    pub topic_events: TopicEventsHub,
+    pub storage: Arc<dyn Storage>,
+    pub mailer: Arc<dyn Mailer>,
 }
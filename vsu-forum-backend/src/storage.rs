@@ -0,0 +1,79 @@
+use std::{fmt::Debug, io::ErrorKind, path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use async_trait::async_trait;
+
+use crate::config::StorageConfig;
+
+/// Keeps uploaded files by keys generated by the application, so
+/// implementations may rely on a key being a short string of ASCII letters,
+/// digits and dots.
+#[async_trait]
+pub trait Storage: Debug + Send + Sync {
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()>;
+
+    /// Returns `None` if there is no file with such key.
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Deleting a missing file is not an error.
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+pub async fn build_storage(config: &StorageConfig) -> anyhow::Result<Arc<dyn Storage>> {
+    match config {
+        StorageConfig::Local { path } => {
+            tokio::fs::create_dir_all(path)
+                .await
+                .with_context(|| format!("cannot create storage directory {}", path.display()))?;
+
+            Ok(Arc::new(LocalStorage { root: path.clone() }))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        let is_valid = !key.is_empty()
+            && !key.starts_with('.')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '.');
+        anyhow::ensure!(is_valid, "invalid storage key {key:?}");
+
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: &[u8]) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+        // written under another name first, so a half-written file is never read
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        tokio::fs::write(&tmp_path, data).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
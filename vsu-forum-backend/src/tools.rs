@@ -1,7 +1,273 @@
+use argon2::{
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Algorithm, Argon2, Params, Version,
+};
+use std::sync::LazyLock;
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Options, Parser};
 use sha3::{Digest, Sha3_256};
+use subtle::ConstantTimeEq;
+use tokio::sync::OnceCell;
+
+use crate::config::PasswordHashingConfig;
+
+/// Prefix of password hashes created before the switch to Argon2id:
+/// `$sha3$<hex of unsalted SHA3-256>`.
+const LEGACY_SHA3_PREFIX: &str = "$sha3$";
+
+pub enum PasswordVerification {
+    Invalid,
+    Valid,
+    /// The password is correct, but the hash is legacy or was created with
+    /// other cost parameters, so it should be replaced.
+    ValidNeedsRehash,
+}
+
+pub async fn hash_password(
+    password: String,
+    config: PasswordHashingConfig,
+) -> anyhow::Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = build_hasher(&config)?
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|err| anyhow::anyhow!("cannot hash password: {err}"))?;
+
+        Ok(hash.to_string())
+    })
+    .await?
+}
+
+pub async fn verify_password(
+    password: String,
+    password_hash: String,
+    config: PasswordHashingConfig,
+) -> anyhow::Result<PasswordVerification> {
+    tokio::task::spawn_blocking(move || {
+        if let Some(legacy_hash) = password_hash.strip_prefix(LEGACY_SHA3_PREFIX) {
+            let mut hasher = Sha3_256::new();
+            hasher.update(password.as_bytes());
+            let hash = hex_encode(&hasher.finalize());
+
+            return Ok(
+                if bool::from(hash.as_bytes().ct_eq(legacy_hash.as_bytes())) {
+                    PasswordVerification::ValidNeedsRehash
+                } else {
+                    PasswordVerification::Invalid
+                },
+            );
+        }
+
+        // nobody can log in with a broken hash, but it is not a server error
+        let parsed_hash = match PasswordHash::new(&password_hash) {
+            Ok(parsed_hash) => parsed_hash,
+            Err(err) => {
+                log::warn!("cannot parse stored password hash: {err}");
+                return Ok(PasswordVerification::Invalid);
+            }
+        };
 
-pub fn hash_text(text: String) -> Vec<u8> {
+        if build_hasher(&config)?
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .is_err()
+        {
+            return Ok(PasswordVerification::Invalid);
+        }
+
+        let params_changed = Params::try_from(&parsed_hash)
+            .map(|params| {
+                params.m_cost() != config.memory_cost
+                    || params.t_cost() != config.time_cost
+                    || params.p_cost() != config.parallelism
+            })
+            .unwrap_or(true);
+
+        Ok(if params_changed {
+            PasswordVerification::ValidNeedsRehash
+        } else {
+            PasswordVerification::Valid
+        })
+    })
+    .await?
+}
+
+static DUMMY_PASSWORD_HASH: OnceCell<String> = OnceCell::const_new();
+
+/// Hash of a random password to verify against when the user does not exist,
+/// so response time does not tell whether a login is registered.
+pub async fn dummy_password_hash(config: &PasswordHashingConfig) -> anyhow::Result<&'static str> {
+    DUMMY_PASSWORD_HASH
+        .get_or_try_init(|| hash_password(generate_secret_token(), config.clone()))
+        .await
+        .map(String::as_str)
+}
+
+fn build_hasher(config: &PasswordHashingConfig) -> anyhow::Result<Argon2<'static>> {
+    let params = Params::new(
+        config.memory_cost,
+        config.time_cost,
+        config.parallelism,
+        None,
+    )
+    .map_err(|err| anyhow::anyhow!("invalid password hashing params: {err}"))?;
+
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+/// Refresh and password reset tokens are random, so unlike passwords they can
+/// be stored as a plain SHA3 digest.
+pub fn generate_secret_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex_encode(&bytes)
+}
+
+/// Random name of an uploaded file in the storage.
+pub fn generate_storage_key() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    hex_encode(&bytes)
+}
+
+pub fn hash_secret_token(refresh_token: &str) -> String {
     let mut hasher = Sha3_256::new();
-    hasher.update(text.as_bytes());
-    hasher.finalize().to_vec()
+    hasher.update(refresh_token.as_bytes());
+    hex_encode(&hasher.finalize())
+}
+
+static HTML_SANITIZER: LazyLock<Builder<'static>> = LazyLock::new(|| {
+    let mut builder = Builder::default();
+    builder
+        // keeps `language-*` classes of code blocks for syntax highlighting
+        .add_tag_attributes("code", ["class"])
+        .link_rel(Some("noopener noreferrer nofollow"));
+    builder
+});
+
+/// Renders post Markdown into HTML that is safe to insert into a page as is:
+/// scripts, event handlers and unsafe link schemes are stripped.
+pub fn render_markdown(text: &str) -> String {
+    let parser = Parser::new_ext(
+        text,
+        Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS,
+    );
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    HTML_SANITIZER.clean(&unsafe_html).to_string()
+}
+
+/// Mentions after this many are ignored.
+const MAX_MENTIONS: usize = 20;
+
+/// Finds distinct `@login` mentions. A mention starts a word, so e-mail
+/// addresses are skipped, and a dot right after it ends the sentence rather
+/// than belongs to the login.
+pub fn extract_mentions(text: &str) -> Vec<String> {
+    let mut mentions: Vec<String> = Vec::new();
+    let mut prev_char: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        prev_char = if c == '@' && !prev_char.is_some_and(is_login_char) {
+            let start = i + c.len_utf8();
+            let mut end = start;
+            while let Some(&(j, c)) = chars.peek() {
+                if !is_login_char(c) {
+                    break;
+                }
+                end = j + c.len_utf8();
+                chars.next();
+            }
+
+            let login = text[start..end].trim_end_matches('.');
+            if !login.is_empty() && !mentions.iter().any(|m| m == login) {
+                mentions.push(login.to_string());
+                if mentions.len() == MAX_MENTIONS {
+                    break;
+                }
+            }
+
+            text[..end].chars().next_back()
+        } else {
+            Some(c)
+        };
+    }
+
+    mentions
+}
+
+fn is_login_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_are_distinct_and_in_order() {
+        assert_eq!(
+            extract_mentions("@bob, @alice and @bob again"),
+            ["bob", "alice"]
+        );
+    }
+
+    #[test]
+    fn mentions_skip_email_addresses_and_trailing_dots() {
+        assert_eq!(
+            extract_mentions("write to bob@example.com or ask @john.doe."),
+            ["john.doe"]
+        );
+        assert!(extract_mentions("@ @@ nobody").is_empty());
+    }
+
+    #[test]
+    fn mentions_allow_non_ascii_logins() {
+        assert_eq!(extract_mentions("привет, @иван_1!"), ["иван_1"]);
+    }
+
+    #[test]
+    fn mentions_are_limited() {
+        let text: String = (0..MAX_MENTIONS + 5)
+            .map(|i| format!("@user{i} "))
+            .collect();
+        assert_eq!(extract_mentions(&text).len(), MAX_MENTIONS);
+    }
+
+    #[test]
+    fn markdown_is_rendered() {
+        let html = render_markdown("**bold** ~~gone~~\n\n```rust\nfn main() {}\n```");
+
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(html.contains("<del>gone</del>"));
+        assert!(html.contains(r#"<code class="language-rust">"#));
+    }
+
+    #[test]
+    fn markdown_html_is_sanitised() {
+        let html = render_markdown("<script>alert(1)</script>\n\n<img src=x onerror=alert(1)>");
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onerror"));
+
+        let html = render_markdown("[link](javascript:alert(1))");
+        assert!(!html.contains("javascript:"));
+    }
+
+    #[test]
+    fn markdown_links_get_safe_rel() {
+        let html = render_markdown("[site](https://example.com)");
+
+        assert!(html.contains(r#"href="https://example.com""#));
+        assert!(html.contains(r#"rel="noopener noreferrer nofollow""#));
+    }
 }
@@ -0,0 +1,316 @@
+use std::io::Cursor;
+
+use axum::{
+    extract::multipart::Field,
+    http::{
+        header::{CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_TYPE, X_CONTENT_TYPE_OPTIONS},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use image::{ImageFormat, ImageReader, Limits};
+
+use crate::{
+    config::AttachmentsConfig, errors::ApiError, storage::Storage, tools::generate_storage_key,
+};
+
+/// Larger images are rejected without decoding, so that a small file cannot
+/// make the server allocate gigabytes for pixels.
+const MAX_IMAGE_SIDE: u32 = 10_000;
+
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+/// An uploaded file that passed the validation.
+pub struct ProcessedUpload {
+    pub content_type: String,
+    pub data: Vec<u8>,
+    pub image: Option<ProcessedImage>,
+}
+
+pub struct ProcessedImage {
+    pub width: u32,
+    pub height: u32,
+    /// PNG that fits into `thumbnail_size` square.
+    pub thumbnail: Vec<u8>,
+}
+
+/// Keys of a stored file and its thumbnail.
+pub struct StoredUpload {
+    pub storage_key: String,
+    pub thumbnail_key: Option<String>,
+}
+
+/// Validates the content type and size of the file. The content type of
+/// images is detected from the data instead of trusting the client, and
+/// they are decoded to make a thumbnail.
+pub async fn process_upload(
+    config: &AttachmentsConfig,
+    declared_content_type: Option<&str>,
+    data: Vec<u8>,
+) -> Result<ProcessedUpload, ApiError> {
+    if data.is_empty() {
+        return Err(ApiError::BadRequest("file is empty".to_string()));
+    }
+    if data.len() > config.max_file_size_bytes {
+        return Err(file_too_large(config));
+    }
+
+    let image_format = image::guess_format(&data).ok().filter(|format| {
+        matches!(
+            format,
+            ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP
+        )
+    });
+
+    let content_type = match image_format {
+        Some(format) => format.to_mime_type().to_string(),
+        None => declared_content_type
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|content_type| content_type.trim().to_ascii_lowercase())
+            .unwrap_or_else(|| "application/octet-stream".to_string()),
+    };
+
+    if !config.allowed_content_types.contains(&content_type) {
+        return Err(ApiError::BadRequest(format!(
+            "files of type {content_type} are not allowed"
+        )));
+    }
+
+    let Some(format) = image_format else {
+        if content_type.starts_with("image/") {
+            return Err(ApiError::BadRequest(
+                "file is not a valid image".to_string(),
+            ));
+        }
+
+        return Ok(ProcessedUpload {
+            content_type,
+            data,
+            image: None,
+        });
+    };
+
+    let thumbnail_size = config.thumbnail_size;
+    let (data, image) = tokio::task::spawn_blocking(move || {
+        let image = make_thumbnail(&data, format, thumbnail_size);
+        (data, image)
+    })
+    .await
+    .map_err(|_| ApiError::InternalServerError)?;
+
+    Ok(ProcessedUpload {
+        content_type,
+        data,
+        image: Some(
+            image.map_err(|_| ApiError::BadRequest("file is not a valid image".to_string()))?,
+        ),
+    })
+}
+
+fn file_too_large(config: &AttachmentsConfig) -> ApiError {
+    ApiError::OtherError(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!(
+            "file must not be larger than {} bytes",
+            config.max_file_size_bytes
+        ),
+    )
+}
+
+fn make_thumbnail(
+    data: &[u8],
+    format: ImageFormat,
+    thumbnail_size: u32,
+) -> image::ImageResult<ProcessedImage> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_SIDE);
+    limits.max_image_height = Some(MAX_IMAGE_SIDE);
+
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let image = reader.decode()?;
+
+    let mut thumbnail = Vec::new();
+    image
+        .thumbnail(thumbnail_size, thumbnail_size)
+        .write_to(&mut Cursor::new(&mut thumbnail), ImageFormat::Png)?;
+
+    Ok(ProcessedImage {
+        width: image.width(),
+        height: image.height(),
+        thumbnail,
+    })
+}
+
+pub async fn store_upload(
+    storage: &dyn Storage,
+    upload: &ProcessedUpload,
+) -> anyhow::Result<StoredUpload> {
+    let storage_key = generate_storage_key();
+    storage.put(&storage_key, &upload.data).await?;
+
+    let thumbnail_key = match &upload.image {
+        Some(image) => {
+            let thumbnail_key = format!("{storage_key}.thumb");
+            if let Err(err) = storage.put(&thumbnail_key, &image.thumbnail).await {
+                delete_stored_files(storage, [storage_key]).await;
+                return Err(err);
+            }
+            Some(thumbnail_key)
+        }
+        None => None,
+    };
+
+    Ok(StoredUpload {
+        storage_key,
+        thumbnail_key,
+    })
+}
+
+/// Files are deleted after the rows referring to them, so a failure only
+/// leaves garbage in the storage and is logged instead of being returned.
+pub async fn delete_stored_files(storage: &dyn Storage, keys: impl IntoIterator<Item = String>) {
+    for key in keys {
+        if let Err(err) = storage.delete(&key).await {
+            log::warn!("cannot delete file {key} from storage: {err}");
+        }
+    }
+}
+
+/// Reads a file from the multipart field and validates it with
+/// [`process_upload`]; the size limit is checked while reading, so too large
+/// files are rejected before they are received completely.
+pub async fn read_upload(
+    mut field: Field<'_>,
+    config: &AttachmentsConfig,
+) -> Result<(String, ProcessedUpload), ApiError> {
+    let file_name = sanitize_file_name(field.file_name())?;
+    let content_type = field.content_type().map(str::to_string);
+
+    let mut data = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|err| ApiError::BadRequest(err.body_text()))?
+    {
+        if data.len() + chunk.len() > config.max_file_size_bytes {
+            return Err(file_too_large(config));
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    let upload = process_upload(config, content_type.as_deref(), data).await?;
+    Ok((file_name, upload))
+}
+
+/// Responds with a stored file. Anything but images is downloaded rather
+/// than opened by the browser.
+pub async fn stored_file_response(
+    storage: &dyn Storage,
+    key: &str,
+    content_type: String,
+    file_name: &str,
+) -> Result<Response, ApiError> {
+    let data = storage
+        .get(key)
+        .await
+        .map_err(|err| {
+            log::warn!("cannot read file {key} from storage: {err}");
+            ApiError::InternalServerError
+        })?
+        .ok_or_else(|| {
+            log::warn!("file {key} is missing in storage");
+            ApiError::NotFound("file not found".to_string())
+        })?;
+
+    let disposition = if content_type.starts_with("image/") {
+        "inline"
+    } else {
+        "attachment"
+    };
+
+    Ok((
+        [
+            (CONTENT_TYPE, content_type),
+            (
+                CONTENT_DISPOSITION,
+                content_disposition(disposition, file_name),
+            ),
+            (X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+            (CACHE_CONTROL, "public, max-age=86400".to_string()),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+/// Keeps only the last component of the path the client may send and drops
+/// control characters.
+fn sanitize_file_name(file_name: Option<&str>) -> Result<String, ApiError> {
+    let file_name: String = file_name
+        .unwrap_or_default()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect();
+
+    let file_name = file_name.trim();
+    if file_name.is_empty() {
+        return Err(ApiError::BadRequest("file name is required".to_string()));
+    }
+
+    Ok(file_name.to_string())
+}
+
+/// `filename` is an ASCII fallback for old clients, `filename*` keeps the
+/// original name percent-encoded as RFC 6266 says.
+fn content_disposition(disposition: &str, file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{b:02X}")
+            }
+        })
+        .collect();
+
+    format!("{disposition}; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_file_name_is_kept() {
+        assert_eq!(
+            content_disposition("inline", "report 1.pdf"),
+            r#"inline; filename="report 1.pdf"; filename*=UTF-8''report%201.pdf"#
+        );
+    }
+
+    #[test]
+    fn quotes_and_non_ascii_are_escaped() {
+        assert_eq!(
+            content_disposition("attachment", "\"отчёт\"\r\n.txt"),
+            "attachment; filename=\"_________.txt\"; \
+             filename*=UTF-8''%22%D0%BE%D1%82%D1%87%D1%91%D1%82%22%0D%0A.txt"
+        );
+    }
+}
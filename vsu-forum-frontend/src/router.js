@@ -1,6 +1,8 @@
 import { createRouter, createWebHistory } from "vue-router";
 import UserRegister from "./components/UserRegister.vue";
 import UserLogin from "./components/UserLogin.vue";
+import PasswordReset from "./components/PasswordReset.vue";
+import EmailVerification from "./components/EmailVerification.vue";
 import UserProfile from "./components/UserProfile.vue";
 import MainPage from "./components/MainPage.vue";
 import TopicPage from "./components/TopicPage.vue";
@@ -20,6 +22,16 @@ const routes = [
     },
     { path: "/register", component: UserRegister, name: "Register" },
     { path: "/login", component: UserLogin, name: "Login" },
+    {
+        path: "/password-reset",
+        component: PasswordReset,
+        name: "PasswordReset",
+    },
+    {
+        path: "/verify-email",
+        component: EmailVerification,
+        name: "EmailVerification",
+    },
     { path: "/profile", component: UserProfile, name: "Profile" },
     {
         path: "/available-reactions",
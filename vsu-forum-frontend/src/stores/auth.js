@@ -3,21 +3,98 @@ import { defineStore } from "pinia";
 
 export const useAuthStore = defineStore("auth", () => {
     const token = ref(localStorage.getItem("token"));
+    const refreshToken = ref(localStorage.getItem("refreshToken"));
+    // shared by requests that get 401 at the same time, because a refresh
+    // token works only once
+    let refreshing = null;
 
     const isAuthorized = computed(() => token.value !== null);
 
-    function setToken(newToken) {
+    function setTokens(newToken, newRefreshToken) {
         token.value = newToken;
-        if (newToken) {
-            localStorage.setItem("token", newToken);
-        } else {
-            localStorage.removeItem("token");
+        refreshToken.value = newRefreshToken;
+        store("token", newToken);
+        store("refreshToken", newRefreshToken);
+    }
+
+    function clear() {
+        setTokens(null, null);
+    }
+
+    async function refresh() {
+        if (!refreshToken.value) {
+            return false;
         }
+
+        refreshing ??= (async () => {
+            try {
+                const response = await fetch(
+                    "http://localhost:3000/users/refresh",
+                    {
+                        method: "POST",
+                        headers: { "Content-Type": "application/json" },
+                        body: JSON.stringify({
+                            refresh_token: refreshToken.value,
+                        }),
+                    },
+                );
+
+                if (!response.ok) {
+                    clear();
+                    return false;
+                }
+
+                const data = await response.json();
+                setTokens(data.token, data.refresh_token);
+                return true;
+            } catch (error) {
+                console.error("Ошибка сети:", error);
+                return false;
+            } finally {
+                refreshing = null;
+            }
+        })();
+
+        return refreshing;
+    }
+
+    // fetch() that sends the access token and, when it has expired, refreshes
+    // it and repeats the request once
+    async function authFetch(url, options = {}) {
+        const send = () =>
+            fetch(url, {
+                ...options,
+                headers: {
+                    ...options.headers,
+                    ...(token.value && {
+                        Authorization: `Bearer ${token.value}`,
+                    }),
+                },
+            });
+
+        const response = await send();
+        if (response.status !== 401 || !token.value) {
+            return response;
+        }
+
+        return (await refresh()) ? send() : response;
     }
 
     return {
         token,
+        refreshToken,
         isAuthorized,
-        setToken,
+        setTokens,
+        clear,
+        refresh,
+        authFetch,
     };
 });
+
+function store(key, value) {
+    if (value) {
+        localStorage.setItem(key, value);
+    } else {
+        localStorage.removeItem(key);
+    }
+}
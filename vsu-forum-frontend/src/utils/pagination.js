@@ -0,0 +1,21 @@
+import { useAuthStore } from "../stores/auth";
+
+// Loads one page of a cursor-paginated endpoint; `nextCursor` is null on the
+// last page. On failure `response` is the failed response, so callers can read
+// the error from it.
+export async function fetchPage(url, cursor = null, limit = 20) {
+    const authStore = useAuthStore();
+    const pageUrl = new URL(url);
+    pageUrl.searchParams.set("limit", String(limit));
+    if (cursor) {
+        pageUrl.searchParams.set("cursor", cursor);
+    }
+
+    const response = await authStore.authFetch(pageUrl);
+    if (!response.ok) {
+        return { response, items: [], nextCursor: null };
+    }
+
+    const page = await response.json();
+    return { response, items: page.items, nextCursor: page.next_cursor };
+}